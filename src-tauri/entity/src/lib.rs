@@ -3,6 +3,7 @@
 pub mod prelude;
 
 pub mod clients;
+pub mod credentials;
 pub mod inventory_mouvements;
 pub mod invoice_items;
 pub mod invoices;
@@ -12,3 +13,4 @@ pub mod products;
 pub mod quote_items;
 pub mod quotes;
 pub mod suppliers;
+pub mod vault;
@@ -1,6 +1,7 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.14
 
 pub use super::clients::{self, ActiveModel as ClientActiveModel, Entity as Clients, Model as ClientModel};
+pub use super::credentials::{self, ActiveModel as CredentialActiveModel, Entity as Credentials, Model as CredentialModel};
 pub use super::inventory_mouvements::{self, ActiveModel as InventoryActiveModel, Entity as InventoryMouvements, Model as InventoryModel};
 pub use super::invoice_items::{self, ActiveModel as InvoiceItemActiveModel, Entity as InvoiceItems, Model as InvoiceItemModel};
 pub use super::invoices::{self, ActiveModel as InvoiceActiveModel, Entity as Invoices, Model as InvoiceModel};
@@ -10,3 +11,4 @@ pub use super::products::{self, ActiveModel as ProductActiveModel, Entity as Pro
 pub use super::quote_items::{self, ActiveModel as QuoteItemActiveModel, Entity as QuoteItems, Model as QuoteItemModel};
 pub use super::quotes::{self, ActiveModel as QuoteActiveModel, Entity as Quotes, Model as QuoteModel};
 pub use super::suppliers::{self, ActiveModel as SupplierActiveModel, Entity as Suppliers, Model as SupplierModel};
+pub use super::vault::{self, ActiveModel as VaultActiveModel, Entity as Vault, Model as VaultModel};
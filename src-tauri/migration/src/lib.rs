@@ -5,6 +5,7 @@ mod utils;
 mod m20220101_000001_init_;
 mod m20240216_201704_seed_init_;
 mod m20240224_175322_status_index_;
+mod m20240310_093000_credentials_;
 pub struct Migrator;
 
 #[async_trait::async_trait]
@@ -15,6 +16,7 @@ impl MigratorTrait for Migrator {
             Box::new(m20220101_000001_init_::Migration),
             Box::new(m20240216_201704_seed_init_::Migration),
             Box::new(m20240224_175322_status_index_::Migration),
+            Box::new(m20240310_093000_credentials_::Migration),
         ]
     }
     //
@@ -0,0 +1,112 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 口令派生参数和校验密文，只有一行
+        manager
+            .create_table(
+                Table::create()
+                    .table(Vault::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Vault::Id).string().not_null().primary_key())
+                    .col(ColumnDef::new(Vault::Salt).string().not_null())
+                    .col(ColumnDef::new(Vault::MemoryCost).integer().not_null())
+                    .col(ColumnDef::new(Vault::TimeCost).integer().not_null())
+                    .col(ColumnDef::new(Vault::Parallelism).integer().not_null())
+                    .col(ColumnDef::new(Vault::Verifier).string().not_null())
+                    .col(
+                        ColumnDef::new(Vault::CreatedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(Credential::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Credential::Id).string().not_null().primary_key())
+                    .col(ColumnDef::new(Credential::Kind).string().not_null())
+                    .col(ColumnDef::new(Credential::Account).string().not_null())
+                    .col(ColumnDef::new(Credential::Secret).string().not_null())
+                    .col(
+                        ColumnDef::new(Credential::CreatedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(Credential::UpdatedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .table(Credential::Table)
+                    .col(Credential::Kind)
+                    .col(Credential::Account)
+                    .unique()
+                    .name("idx_credentials_kind_account")
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(Credential::Table).to_owned()).await?;
+        manager.drop_table(Table::drop().table(Vault::Table).to_owned()).await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum Vault {
+    #[sea_orm(iden = "vault")]
+    Table,
+    Id,
+    #[sea_orm(iden = "salt")]
+    Salt,
+    #[sea_orm(iden = "memory_cost")]
+    MemoryCost,
+    #[sea_orm(iden = "time_cost")]
+    TimeCost,
+    #[sea_orm(iden = "parallelism")]
+    Parallelism,
+    #[sea_orm(iden = "verifier")]
+    Verifier,
+    #[sea_orm(iden = "created_at")]
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+pub enum Credential {
+    #[sea_orm(iden = "credentials")]
+    Table,
+    Id,
+    #[sea_orm(iden = "kind")]
+    Kind,
+    #[sea_orm(iden = "account")]
+    Account,
+    #[sea_orm(iden = "secret")]
+    Secret,
+    #[sea_orm(iden = "created_at")]
+    CreatedAt,
+    #[sea_orm(iden = "updated_at")]
+    UpdatedAt,
+}
@@ -0,0 +1,41 @@
+use sea_orm::FromQueryResult;
+use serde::{Deserialize, Serialize};
+
+// vault 表只有一行
+pub const VAULT_ID: &str = "default";
+
+// 列表只返回账号信息，不包含密文
+#[derive(Deserialize, Serialize, Debug, PartialEq, FromQueryResult)]
+pub struct SelectCredentials {
+    pub id: String,
+    pub kind: String,
+    pub account: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+// secret 是已经加密的密文，service 层不接触明文
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewCredential {
+    pub kind: String,
+    pub account: String,
+    pub secret: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Credential {
+    pub id: String,
+    pub kind: String,
+    pub account: String,
+    pub secret: String,
+}
+
+// Argon2 参数和用于校验口令的密文
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultParams {
+    pub salt: String,
+    pub memory_cost: u32,
+    pub time_cost: u32,
+    pub parallelism: u32,
+    pub verifier: String,
+}
@@ -0,0 +1,26 @@
+use sea_orm::FromQueryResult;
+use serde::{Deserialize, Serialize};
+
+use crate::{NewClient, NewInventory, NewProduct, NewSupplier};
+
+// CSV 导入中已通过校验的一行，按目标表区分
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "table", content = "record", rename_all = "snake_case")]
+pub enum ImportRecord {
+    Client(NewClient),
+    Supplier(NewSupplier),
+    // 与已有记录重复且选择更新时，只覆盖 CSV 中非空的字段
+    ClientUpdate { id: String, client: NewClient },
+    SupplierUpdate { id: String, supplier: NewSupplier },
+    Product(NewProduct),
+    Inventory(NewInventory),
+}
+
+// 查重时用到的客户/供应商字段
+#[derive(Debug, Clone, Serialize, Deserialize, FromQueryResult)]
+pub struct ContactIdentity {
+    pub id: String,
+    pub full_name: String,
+    pub phone_number: Option<String>,
+    pub email: Option<String>,
+}
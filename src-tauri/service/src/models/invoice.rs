@@ -34,6 +34,23 @@ pub struct NewInvoice {
     pub paid_amount: f64,
 }
 
+// 发票连同明细一次性创建，每一行对应一条 OUT 库存记录
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewInvoiceWithItems {
+    pub client_id: String,
+    pub order_id: Option<String>,
+    pub status: String,
+    pub paid_amount: f64,
+    pub items: Vec<NewInvoiceLine>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewInvoiceLine {
+    pub product_id: String,
+    pub quantity: f64,
+    pub price: f64,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Invoice {
     pub id: String,
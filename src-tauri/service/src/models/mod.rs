@@ -1,5 +1,7 @@
 mod client;
+mod credential;
 mod dashboard;
+mod import;
 mod inventory;
 mod invoice;
 mod invoice_item;
@@ -11,7 +13,9 @@ mod quote_item;
 mod supplier;
 
 pub use client::*;
+pub use credential::*;
 pub use dashboard::*;
+pub use import::*;
 pub use inventory::*;
 pub use invoice::*;
 pub use invoice_item::*;
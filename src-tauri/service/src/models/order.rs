@@ -30,6 +30,21 @@ pub struct NewOrder {
     pub status: String,
 }
 
+// 订单连同明细一次性创建，每一行对应一条 OUT 库存记录
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewOrderWithItems {
+    pub client_id: String,
+    pub status: String,
+    pub items: Vec<NewOrderLine>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewOrderLine {
+    pub product_id: String,
+    pub quantity: f64,
+    pub price: f64,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Order {
     pub id: String,
@@ -27,6 +27,20 @@ pub struct NewQuote {
     pub client_id: String,
 }
 
+// 报价单连同明细一次性创建（报价不影响库存）
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewQuoteWithItems {
+    pub client_id: String,
+    pub items: Vec<NewQuoteLine>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewQuoteLine {
+    pub product_id: String,
+    pub quantity: f64,
+    pub price: f64,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Quote {
     pub id: String,
@@ -1,10 +1,26 @@
 use ::entity::prelude::*;
-use sea_orm::*;
+use sea_orm::{sea_query::Expr, *};
+use serde_json::json;
 
 use crate::{models::*, Order};
 
 pub struct MutationsService;
 
+fn not_found(entity: &str, id: &str) -> DbErr {
+    DbErr::RecordNotFound(format!("{} {} not found", entity, id))
+}
+
+// 在事务中为一行明细生成 OUT 库存记录，返回库存记录 id
+async fn insert_out_mvm<C: ConnectionTrait>(db: &C, product_id: String, quantity: f64) -> Result<String, DbErr> {
+    let mvm = InventoryActiveModel {
+        mvm_type: ActiveValue::Set(String::from("OUT")),
+        quantity: ActiveValue::Set(quantity),
+        product_id: ActiveValue::Set(product_id),
+        ..Default::default()
+    };
+    Ok(mvm.insert(db).await?.id)
+}
+
 impl MutationsService {
     pub async fn create_product(db: &DbConn, product: NewProduct) -> Result<String, DbErr> {
         let product = ProductActiveModel {
@@ -21,8 +37,11 @@ impl MutationsService {
         }
     }
     pub async fn update_product(db: &DbConn, product: Product) -> Result<(), DbErr> {
-        let product_model = Products::find_by_id(product.id).one(db).await?;
-        let mut product_active: ProductActiveModel = product_model.unwrap().into();
+        let product_model = Products::find_by_id(product.id.clone())
+            .one(db)
+            .await?
+            .ok_or_else(|| not_found("product", &product.id))?;
+        let mut product_active: ProductActiveModel = product_model.into();
         product_active.name = ActiveValue::Set(product.name);
         product_active.price = ActiveValue::Set(product.price);
         product_active.image = ActiveValue::Set(product.image);
@@ -59,8 +78,11 @@ impl MutationsService {
         }
     }
     pub async fn update_client(db: &DbConn, client: Client) -> Result<(), DbErr> {
-        let client_model = Clients::find_by_id(client.id).one(db).await?;
-        let mut client_active: ClientActiveModel = client_model.unwrap().into();
+        let client_model = Clients::find_by_id(client.id.clone())
+            .one(db)
+            .await?
+            .ok_or_else(|| not_found("client", &client.id))?;
+        let mut client_active: ClientActiveModel = client_model.into();
         client_active.full_name = ActiveValue::Set(client.full_name);
         client_active.email = ActiveValue::Set(client.email);
         client_active.phone_number = ActiveValue::Set(client.phone_number);
@@ -97,8 +119,11 @@ impl MutationsService {
         }
     }
     pub async fn update_supplier(db: &DbConn, supplier: Supplier) -> Result<(), DbErr> {
-        let supplier_model = Suppliers::find_by_id(supplier.id).one(db).await?;
-        let mut supplier_active: SupplierActiveModel = supplier_model.unwrap().into();
+        let supplier_model = Suppliers::find_by_id(supplier.id.clone())
+            .one(db)
+            .await?
+            .ok_or_else(|| not_found("supplier", &supplier.id))?;
+        let mut supplier_active: SupplierActiveModel = supplier_model.into();
         supplier_active.full_name = ActiveValue::Set(supplier.full_name);
         supplier_active.email = ActiveValue::Set(supplier.email);
         supplier_active.phone_number = ActiveValue::Set(supplier.phone_number);
@@ -143,8 +168,11 @@ impl MutationsService {
         }
     }
     pub async fn update_inv_mvm(db: &DbConn, mvm: Inventory) -> Result<(), DbErr> {
-        let inventory_model = InventoryMouvements::find_by_id(mvm.id).one(db).await?;
-        let mut inventory_active: InventoryActiveModel = inventory_model.unwrap().into();
+        let inventory_model = InventoryMouvements::find_by_id(mvm.id.clone())
+            .one(db)
+            .await?
+            .ok_or_else(|| not_found("inventory movement", &mvm.id))?;
+        let mut inventory_active: InventoryActiveModel = inventory_model.into();
         inventory_active.mvm_type = ActiveValue::Set(mvm.mvm_type);
         inventory_active.quantity = ActiveValue::Set(mvm.quantity);
         inventory_active.product_id = ActiveValue::Set(mvm.product_id);
@@ -165,9 +193,51 @@ impl MutationsService {
             Err(err) => Err(err),
         }
     }
+    // 订单、库存记录和订单明细在同一个事务中创建，任一步失败全部回滚；
+    // 返回值由事务中插入的行组成，提交后不再查询，避免已提交的数据被报告为失败
+    pub async fn create_order_with_items(db: &DbConn, order: NewOrderWithItems) -> Result<JsonValue, DbErr> {
+        let txn = db.begin().await?;
+        let created = OrderActiveModel {
+            client_id: ActiveValue::Set(order.client_id),
+            status: ActiveValue::Set(order.status),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+        let mut items = Vec::<JsonValue>::new();
+        for item in order.items {
+            let inventory_id = insert_out_mvm(&txn, item.product_id.clone(), item.quantity).await?;
+            let order_item = OrderItemActiveModel {
+                order_id: ActiveValue::Set(created.id.clone()),
+                inventory_id: ActiveValue::Set(inventory_id),
+                price: ActiveValue::Set(item.price),
+                ..Default::default()
+            }
+            .insert(&txn)
+            .await?;
+            items.push(json!({
+                "id": order_item.id,
+                "inventory_id": order_item.inventory_id,
+                "product_id": item.product_id,
+                "price": order_item.price,
+                "quantity": item.quantity,
+            }));
+        }
+        txn.commit().await?;
+        Ok(json!({
+            "id": created.id,
+            "clientId": created.client_id,
+            "createdAt": created.created_at,
+            "status": created.status,
+            "items": items,
+        }))
+    }
     pub async fn update_order(db: &DbConn, order: Order) -> Result<(), DbErr> {
-        let order_model = Orders::find_by_id(order.id).one(db).await?;
-        let mut order_active: OrderActiveModel = order_model.unwrap().into();
+        let order_model = Orders::find_by_id(order.id.clone())
+            .one(db)
+            .await?
+            .ok_or_else(|| not_found("order", &order.id))?;
+        let mut order_active: OrderActiveModel = order_model.into();
         order_active.client_id = ActiveValue::Set(order.client_id);
         order_active.status = ActiveValue::Set(order.status);
         match order_active.save(db).await {
@@ -199,8 +269,11 @@ impl MutationsService {
         }
     }
     pub async fn update_order_item(db: &DbConn, item: OrderItem) -> Result<(), DbErr> {
-        let order_item_model = OrderItems::find_by_id(item.id).one(db).await?;
-        let mut order_item_active: OrderItemActiveModel = order_item_model.unwrap().into();
+        let order_item_model = OrderItems::find_by_id(item.id.clone())
+            .one(db)
+            .await?
+            .ok_or_else(|| not_found("order item", &item.id))?;
+        let mut order_item_active: OrderItemActiveModel = order_item_model.into();
         order_item_active.order_id = ActiveValue::Set(item.order_id);
         order_item_active.inventory_id = ActiveValue::Set(item.inventory_id);
         order_item_active.price = ActiveValue::Set(item.price);
@@ -234,9 +307,53 @@ impl MutationsService {
             Err(err) => Err(err),
         }
     }
+    // 发票、库存记录和发票明细在同一个事务中创建，任一步失败全部回滚，返回值同样由事务中插入的行组成
+    pub async fn create_invoice_with_items(db: &DbConn, invoice: NewInvoiceWithItems) -> Result<JsonValue, DbErr> {
+        let txn = db.begin().await?;
+        let created = InvoiceActiveModel {
+            client_id: ActiveValue::Set(invoice.client_id),
+            status: ActiveValue::Set(invoice.status),
+            order_id: ActiveValue::Set(invoice.order_id),
+            paid_amount: ActiveValue::Set(invoice.paid_amount),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+        let mut items = Vec::<JsonValue>::new();
+        for item in invoice.items {
+            let inventory_id = insert_out_mvm(&txn, item.product_id.clone(), item.quantity).await?;
+            let invoice_item = InvoiceItemActiveModel {
+                invoice_id: ActiveValue::Set(created.id.clone()),
+                inventory_id: ActiveValue::Set(inventory_id),
+                price: ActiveValue::Set(item.price),
+                ..Default::default()
+            }
+            .insert(&txn)
+            .await?;
+            items.push(json!({
+                "id": invoice_item.id,
+                "inventory_id": invoice_item.inventory_id,
+                "product_id": item.product_id,
+                "price": invoice_item.price,
+                "quantity": item.quantity,
+            }));
+        }
+        txn.commit().await?;
+        Ok(json!({
+            "id": created.id,
+            "clientId": created.client_id,
+            "paidAmount": created.paid_amount,
+            "createdAt": created.created_at,
+            "status": created.status,
+            "items": items,
+        }))
+    }
     pub async fn update_invoice(db: &DbConn, invoice: Invoice) -> Result<(), DbErr> {
-        let invoice_model = Invoices::find_by_id(invoice.id).one(db).await?;
-        let mut invoice_active: InvoiceActiveModel = invoice_model.unwrap().into();
+        let invoice_model = Invoices::find_by_id(invoice.id.clone())
+            .one(db)
+            .await?
+            .ok_or_else(|| not_found("invoice", &invoice.id))?;
+        let mut invoice_active: InvoiceActiveModel = invoice_model.into();
         invoice_active.client_id = ActiveValue::Set(invoice.client_id);
         invoice_active.status = ActiveValue::Set(invoice.status);
         invoice_active.paid_amount = ActiveValue::Set(invoice.paid_amount);
@@ -269,8 +386,11 @@ impl MutationsService {
         }
     }
     pub async fn update_invoice_item(db: &DbConn, item: InvoiceItem) -> Result<(), DbErr> {
-        let invoice_item_model = InvoiceItems::find_by_id(item.id).one(db).await?;
-        let mut invoice_item_active: InvoiceItemActiveModel = invoice_item_model.unwrap().into();
+        let invoice_item_model = InvoiceItems::find_by_id(item.id.clone())
+            .one(db)
+            .await?
+            .ok_or_else(|| not_found("invoice item", &item.id))?;
+        let mut invoice_item_active: InvoiceItemActiveModel = invoice_item_model.into();
         invoice_item_active.invoice_id = ActiveValue::Set(item.invoice_id);
         invoice_item_active.inventory_id = ActiveValue::Set(item.inventory_id);
         invoice_item_active.price = ActiveValue::Set(item.price);
@@ -300,9 +420,47 @@ impl MutationsService {
             Err(err) => Err(err),
         }
     }
+    // 报价单和报价明细在同一个事务中创建，任一步失败全部回滚，返回值同样由事务中插入的行组成
+    pub async fn create_quote_with_items(db: &DbConn, quote: NewQuoteWithItems) -> Result<JsonValue, DbErr> {
+        let txn = db.begin().await?;
+        let created = QuoteActiveModel {
+            client_id: ActiveValue::Set(quote.client_id),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+        let mut items = Vec::<JsonValue>::new();
+        for item in quote.items {
+            let quote_item = QuoteItemActiveModel {
+                product_id: ActiveValue::Set(item.product_id),
+                quote_id: ActiveValue::Set(created.id.clone()),
+                price: ActiveValue::Set(item.price),
+                quantity: ActiveValue::Set(item.quantity),
+                ..Default::default()
+            }
+            .insert(&txn)
+            .await?;
+            items.push(json!({
+                "id": quote_item.id,
+                "product_id": quote_item.product_id,
+                "price": quote_item.price,
+                "quantity": quote_item.quantity,
+            }));
+        }
+        txn.commit().await?;
+        Ok(json!({
+            "id": created.id,
+            "clientId": created.client_id,
+            "createdAt": created.created_at,
+            "items": items,
+        }))
+    }
     pub async fn update_quote(db: &DbConn, quote: Quote) -> Result<(), DbErr> {
-        let quote_model = Quotes::find_by_id(quote.id).one(db).await?;
-        let mut quote_active: QuoteActiveModel = quote_model.unwrap().into();
+        let quote_model = Quotes::find_by_id(quote.id.clone())
+            .one(db)
+            .await?
+            .ok_or_else(|| not_found("quote", &quote.id))?;
+        let mut quote_active: QuoteActiveModel = quote_model.into();
         quote_active.client_id = ActiveValue::Set(quote.client_id);
         match quote_active.save(db).await {
             Ok(_) => Ok(()),
@@ -334,8 +492,11 @@ impl MutationsService {
         }
     }
     pub async fn update_quote_item(db: &DbConn, item: QuoteItem) -> Result<(), DbErr> {
-        let quote_item_model = QuoteItems::find_by_id(item.id).one(db).await?;
-        let mut quote_item_active: QuoteItemActiveModel = quote_item_model.unwrap().into();
+        let quote_item_model = QuoteItems::find_by_id(item.id.clone())
+            .one(db)
+            .await?
+            .ok_or_else(|| not_found("quote item", &item.id))?;
+        let mut quote_item_active: QuoteItemActiveModel = quote_item_model.into();
         quote_item_active.product_id = ActiveValue::Set(item.product_id);
         quote_item_active.quote_id = ActiveValue::Set(item.quote_id);
         quote_item_active.price = ActiveValue::Set(item.price);
@@ -355,5 +516,274 @@ impl MutationsService {
             None => Ok(0),
         }
     }
-    //
+    pub async fn create_vault(db: &DbConn, params: VaultParams) -> Result<(), DbErr> {
+        let vault = VaultActiveModel {
+            id: ActiveValue::Set(VAULT_ID.to_string()),
+            salt: ActiveValue::Set(params.salt),
+            memory_cost: ActiveValue::Set(params.memory_cost as i32),
+            time_cost: ActiveValue::Set(params.time_cost as i32),
+            parallelism: ActiveValue::Set(params.parallelism as i32),
+            verifier: ActiveValue::Set(params.verifier),
+            ..Default::default()
+        };
+        vault.insert(db).await?;
+        Ok(())
+    }
+    // 同一 kind 和 account 只保留一条，已存在时替换密文
+    pub async fn upsert_credential(db: &DbConn, credential: NewCredential) -> Result<String, DbErr> {
+        let existing = Credentials::find()
+            .filter(credentials::Column::Kind.eq(credential.kind.clone()))
+            .filter(credentials::Column::Account.eq(credential.account.clone()))
+            .one(db)
+            .await?;
+        match existing {
+            Some(existing) => {
+                Credentials::update_many()
+                    .col_expr(credentials::Column::Secret, Expr::value(credential.secret))
+                    .col_expr(credentials::Column::UpdatedAt, Expr::current_timestamp().into())
+                    .filter(credentials::Column::Id.eq(existing.id.clone()))
+                    .exec(db)
+                    .await?;
+                Ok(existing.id)
+            }
+            None => {
+                let created = CredentialActiveModel {
+                    kind: ActiveValue::Set(credential.kind),
+                    account: ActiveValue::Set(credential.account),
+                    secret: ActiveValue::Set(credential.secret),
+                    ..Default::default()
+                };
+                Ok(created.insert(db).await?.id)
+            }
+        }
+    }
+    pub async fn delete_credential(db: &DbConn, kind: String, account: String) -> Result<u64, DbErr> {
+        let res = Credentials::delete_many()
+            .filter(credentials::Column::Kind.eq(kind))
+            .filter(credentials::Column::Account.eq(account))
+            .exec(db)
+            .await?;
+        Ok(res.rows_affected)
+    }
+    // 更换口令：新的派生参数和重新加密后的密文在同一个事务中写入
+    pub async fn rotate_vault(db: &DbConn, params: VaultParams, credentials: Vec<Credential>) -> Result<(), DbErr> {
+        let txn = db.begin().await?;
+        let vault = Vault::find_by_id(VAULT_ID)
+            .one(&txn)
+            .await?
+            .ok_or_else(|| not_found("vault", VAULT_ID))?;
+        let mut vault_active: VaultActiveModel = vault.into();
+        vault_active.salt = ActiveValue::Set(params.salt);
+        vault_active.memory_cost = ActiveValue::Set(params.memory_cost as i32);
+        vault_active.time_cost = ActiveValue::Set(params.time_cost as i32);
+        vault_active.parallelism = ActiveValue::Set(params.parallelism as i32);
+        vault_active.verifier = ActiveValue::Set(params.verifier);
+        vault_active.update(&txn).await?;
+        for credential in credentials {
+            Credentials::update_many()
+                .col_expr(credentials::Column::Secret, Expr::value(credential.secret))
+                .col_expr(credentials::Column::UpdatedAt, Expr::current_timestamp().into())
+                .filter(credentials::Column::Id.eq(credential.id))
+                .exec(&txn)
+                .await?;
+        }
+        txn.commit().await
+    }
+    // 清空所有凭据和口令信息，之后可以用新口令重新建立
+    pub async fn wipe_credentials(db: &DbConn) -> Result<u64, DbErr> {
+        let txn = db.begin().await?;
+        let res = Credentials::delete_many().exec(&txn).await?;
+        Vault::delete_many().exec(&txn).await?;
+        txn.commit().await?;
+        Ok(res.rows_affected)
+    }
+    // 整批导入在一个事务中完成；每行使用保存点，单行写入失败只回滚该行。
+    // dry_run 时照常写入以得到真实的校验结果，最后回滚整个事务
+    pub async fn import_records(
+        db: &DbConn,
+        records: Vec<ImportRecord>,
+        dry_run: bool,
+    ) -> Result<Vec<Result<String, DbErr>>, DbErr> {
+        let txn = db.begin().await?;
+        let mut results = Vec::with_capacity(records.len());
+        for record in records {
+            let savepoint = txn.begin().await?;
+            let inserted = match record {
+                ImportRecord::Client(client) => ClientActiveModel {
+                    full_name: ActiveValue::Set(client.full_name),
+                    phone_number: ActiveValue::Set(client.phone_number),
+                    email: ActiveValue::Set(client.email),
+                    address: ActiveValue::Set(client.address),
+                    image: ActiveValue::Set(client.image),
+                    ..Default::default()
+                }
+                .insert(&savepoint)
+                .await
+                .map(|c| c.id),
+                ImportRecord::Supplier(supplier) => SupplierActiveModel {
+                    full_name: ActiveValue::Set(supplier.full_name),
+                    phone_number: ActiveValue::Set(supplier.phone_number),
+                    email: ActiveValue::Set(supplier.email),
+                    address: ActiveValue::Set(supplier.address),
+                    image: ActiveValue::Set(supplier.image),
+                    ..Default::default()
+                }
+                .insert(&savepoint)
+                .await
+                .map(|s| s.id),
+                ImportRecord::ClientUpdate { id, client } => match Clients::find_by_id(id.clone()).one(&savepoint).await {
+                    Ok(Some(model)) => {
+                        let mut active: ClientActiveModel = model.into();
+                        active.full_name = ActiveValue::Set(client.full_name);
+                        if client.email.is_some() {
+                            active.email = ActiveValue::Set(client.email);
+                        }
+                        if client.phone_number.is_some() {
+                            active.phone_number = ActiveValue::Set(client.phone_number);
+                        }
+                        if client.address.is_some() {
+                            active.address = ActiveValue::Set(client.address);
+                        }
+                        if client.image.is_some() {
+                            active.image = ActiveValue::Set(client.image);
+                        }
+                        active.update(&savepoint).await.map(|c| c.id)
+                    }
+                    Ok(None) => Err(not_found("client", &id)),
+                    Err(err) => Err(err),
+                },
+                ImportRecord::SupplierUpdate { id, supplier } => match Suppliers::find_by_id(id.clone()).one(&savepoint).await {
+                    Ok(Some(model)) => {
+                        let mut active: SupplierActiveModel = model.into();
+                        active.full_name = ActiveValue::Set(supplier.full_name);
+                        if supplier.email.is_some() {
+                            active.email = ActiveValue::Set(supplier.email);
+                        }
+                        if supplier.phone_number.is_some() {
+                            active.phone_number = ActiveValue::Set(supplier.phone_number);
+                        }
+                        if supplier.address.is_some() {
+                            active.address = ActiveValue::Set(supplier.address);
+                        }
+                        if supplier.image.is_some() {
+                            active.image = ActiveValue::Set(supplier.image);
+                        }
+                        active.update(&savepoint).await.map(|s| s.id)
+                    }
+                    Ok(None) => Err(not_found("supplier", &id)),
+                    Err(err) => Err(err),
+                },
+                ImportRecord::Product(product) => ProductActiveModel {
+                    name: ActiveValue::Set(product.name),
+                    price: ActiveValue::Set(product.price),
+                    image: ActiveValue::Set(product.image),
+                    description: ActiveValue::Set(product.description),
+                    min_quantity: ActiveValue::Set(product.min_quantity),
+                    ..Default::default()
+                }
+                .insert(&savepoint)
+                .await
+                .map(|p| p.id),
+                ImportRecord::Inventory(mvm) => InventoryActiveModel {
+                    mvm_type: ActiveValue::Set(mvm.mvm_type),
+                    quantity: ActiveValue::Set(mvm.quantity),
+                    product_id: ActiveValue::Set(mvm.product_id),
+                    ..Default::default()
+                }
+                .insert(&savepoint)
+                .await
+                .map(|m| m.id),
+            };
+            match inserted {
+                Ok(id) => {
+                    savepoint.commit().await?;
+                    results.push(Ok(id));
+                }
+                Err(err) => {
+                    savepoint.rollback().await?;
+                    results.push(Err(err));
+                }
+            }
+        }
+        if dry_run {
+            txn.rollback().await?;
+        } else {
+            txn.commit().await?;
+        }
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use migration::{Migrator, MigratorTrait};
+
+    async fn database() -> (DbConn, String, String) {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+        let client_id = MutationsService::create_client(
+            &db,
+            NewClient { full_name: "Client".into(), address: None, phone_number: None, email: None, image: None },
+        )
+        .await
+        .unwrap();
+        let product_id = MutationsService::create_product(
+            &db,
+            NewProduct { name: "Pen".into(), description: None, price: 2.0, min_quantity: 1.0, image: None },
+        )
+        .await
+        .unwrap();
+        (db, client_id, product_id)
+    }
+
+    fn line(product_id: &str) -> NewOrderLine {
+        NewOrderLine { product_id: product_id.into(), quantity: 3.0, price: 2.0 }
+    }
+
+    // 迁移会写入示例数据，这里比较前后的行数
+    async fn counts(db: &DbConn) -> (u64, u64, u64) {
+        (
+            Orders::find().count(db).await.unwrap(),
+            OrderItems::find().count(db).await.unwrap(),
+            InventoryMouvements::find().count(db).await.unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn failing_item_rolls_back_the_order() {
+        let (db, client_id, product_id) = database().await;
+        let before = counts(&db).await;
+        let order = NewOrderWithItems {
+            client_id,
+            status: "PENDING".into(),
+            items: vec![line(&product_id), line("missing")],
+        };
+
+        assert!(MutationsService::create_order_with_items(&db, order).await.is_err());
+        assert_eq!(counts(&db).await, before);
+    }
+
+    #[tokio::test]
+    async fn created_order_is_built_from_inserted_rows() {
+        let (db, client_id, product_id) = database().await;
+        let order = NewOrderWithItems {
+            client_id: client_id.clone(),
+            status: "PENDING".into(),
+            items: vec![line(&product_id)],
+        };
+
+        let created = MutationsService::create_order_with_items(&db, order).await.unwrap();
+        let order_id = created["id"].as_str().unwrap();
+        let item = OrderItems::find()
+            .filter(order_items::Column::OrderId.eq(order_id))
+            .one(&db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(created["clientId"], client_id);
+        assert_eq!(created["items"][0]["id"], item.id);
+        assert_eq!(created["items"][0]["inventory_id"], item.inventory_id);
+        assert_eq!(created["items"][0]["product_id"], product_id);
+    }
 }
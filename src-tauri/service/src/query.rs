@@ -2,13 +2,13 @@ use entity::prelude::*;
 use sea_orm::{
     sea_query::{Alias, Cond, Expr, Func, IntoCondition, Query, SimpleExpr, SqliteQueryBuilder, SubQueryStatement},
     ColumnTrait, Condition, DatabaseConnection as DbConn, DbBackend, DbErr, EntityTrait, FromQueryResult, JoinType, JsonValue, Order, PaginatorTrait,
-    QueryFilter, QuerySelect, QueryTrait, RelationTrait, Statement,
+    QueryFilter, QueryOrder, QuerySelect, QueryTrait, RelationTrait, Statement,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 
 use crate::{
-    SelectClients, SelectExpenses, SelectInventory, SelectTopProducts, SelectInvoiceDetails, SelectInvoices, SelectInvoicesItems, SelectInvoicesItemsForUpdate,
+    ContactIdentity, Credential, SelectCredentials, VaultParams, VAULT_ID, SelectClients, SelectExpenses, SelectInventory, SelectTopProducts, SelectInvoiceDetails, SelectInvoices, SelectInvoicesItems, SelectInvoicesItemsForUpdate,
     SelectMvm, SelectOrderDetails, SelectOrders, SelectOrdersItems, SelectOrdersItemsForUpdate, SelectProducts, SelectQuoteDetails, SelectQuotes,
     SelectQuotesItems, SelectQuotesItemsForUpdate, SelectRevenue, SelectStatusCount, SelectSuppliers, SelectTops,
 };
@@ -22,14 +22,21 @@ pub struct ListArgs {
     pub created_at: Option<String>,
 }
 
+impl ListArgs {
+    // limit 为 0 时不分页，返回全部筛选结果（导出使用）
+    pub fn limit(&self) -> u64 {
+        if self.limit == 0 { i64::MAX as u64 } else { self.limit }
+    }
+    pub fn offset(&self) -> u64 {
+        if self.limit == 0 { 0 } else { self.page.saturating_sub(1) * self.limit }
+    }
+}
+
 pub struct QueriesService;
 
 impl QueriesService {
-    pub async fn list_products(db: &DbConn, args: ListArgs) -> Result<JsonValue, DbErr> {
-        let count = Products::find().filter(
-            Condition::any().add(products::Column::Name.like(format!("{}%", args.search))).add(products::Column::Description.like(format!("%{}%", args.search))),
-        ).count(db).await?;
-        
+    // 列表查询语句与行格式，导出时逐行读取同一查询
+    pub fn list_products_statement(args: &ListArgs) -> Statement {
         let (sql, values) = Query::select().from(Products).exprs([
             Expr::col((Products, products::Column::Id)),
             Expr::col((Products, products::Column::Name)),
@@ -97,23 +104,28 @@ impl QueriesService {
             ).add(
                 Expr::col((Products, products::Column::Description)).like(format!("%{}%", args.search)).into_condition(),
             ),
-        ).limit(args.limit).offset((args.page - 1) * args.limit).order_by(products::Column::CreatedAt, Order::Desc).to_owned().build(SqliteQueryBuilder);
-        
-        let res = SelectProducts::find_by_statement(Statement::from_sql_and_values(DbBackend::Sqlite, sql, values)).all(db).await?;
+        ).limit(args.limit()).offset(args.offset()).order_by(products::Column::CreatedAt, Order::Desc).to_owned().build(SqliteQueryBuilder);
+        Statement::from_sql_and_values(DbBackend::Sqlite, sql, values)
+    }
+    pub fn list_products_row(row: SelectProducts) -> JsonValue {
+        json!({
+            "id": row.id,
+            "name": row.name,
+            "description": row.description,
+            "image": row.image,
+            "price": row.price,
+            "minQuantity": row.min_quantity,
+            "stock": row.stock,
+            "createdAt": row.created_at,
+        })
+    }
+    pub async fn list_products(db: &DbConn, args: ListArgs) -> Result<JsonValue, DbErr> {
+        let count = Products::find().filter(
+            Condition::any().add(products::Column::Name.like(format!("{}%", args.search))).add(products::Column::Description.like(format!("%{}%", args.search))),
+        ).count(db).await?;
         
-        let mut result = Vec::<JsonValue>::new();
-        res.into_iter().for_each(|row| {
-            result.push(json!({
-                "id": row.id,
-                "name": row.name,
-                "description": row.description,
-                "image": row.image,
-                "price": row.price,
-                "minQuantity": row.min_quantity,
-                "stock": row.stock,
-                "createdAt": row.created_at,
-            }));
-        });
+        let res = SelectProducts::find_by_statement(Self::list_products_statement(&args)).all(db).await?;
+        let result: Vec<JsonValue> = res.into_iter().map(Self::list_products_row).collect();
         
         Ok(json!({
             "count": count,
@@ -126,9 +138,7 @@ impl QueriesService {
         Ok(products)
     }
     //
-    pub async fn list_clients(db: &DbConn, args: ListArgs) -> Result<JsonValue, DbErr> {
-        let count = Clients::find().filter(clients::Column::FullName.like(format!("{}%", args.search))).count(db).await?;
-        
+    pub fn list_clients_statement(args: &ListArgs) -> Statement {
         let (sql, values) = Query::select().from(Clients).exprs([
             Expr::col((Clients, clients::Column::Id)),
             Expr::col((Clients, clients::Column::FullName)),
@@ -163,37 +173,54 @@ impl QueriesService {
             Alias::new("credi"),
         ).cond_where(
             Expr::col((Clients, clients::Column::FullName)).like(format!("{}%", args.search)).into_condition(),
-        ).limit(args.limit).offset((args.page - 1) * args.limit).order_by(clients::Column::CreatedAt, Order::Desc).to_owned().build(SqliteQueryBuilder);
+        ).limit(args.limit()).offset(args.offset()).order_by(clients::Column::CreatedAt, Order::Desc).to_owned().build(SqliteQueryBuilder);
+        Statement::from_sql_and_values(DbBackend::Sqlite, sql, values)
+    }
+    pub fn list_clients_row(row: SelectClients) -> JsonValue {
+        json!({
+            "id": row.id,
+            "fullname": row.full_name,
+            "address": row.address,
+            "image": row.image,
+            "email": row.email,
+            "phoneNumber": row.phone_number,
+            "credi": row.credi,
+        })
+    }
+    pub async fn list_clients(db: &DbConn, args: ListArgs) -> Result<JsonValue, DbErr> {
+        let count = Clients::find().filter(clients::Column::FullName.like(format!("{}%", args.search))).count(db).await?;
         
-        let res = SelectClients::find_by_statement(Statement::from_sql_and_values(DbBackend::Sqlite, sql, values)).all(db).await?;
-        
-        let mut result = Vec::<JsonValue>::new();
-        res.into_iter().for_each(|row| {
-            result.push(json!({
-                "id": row.id,
-                "fullname": row.full_name,
-                "address": row.address,
-                "image": row.image,
-                "email": row.email,
-                "phoneNumber": row.phone_number,
-                "credi": row.credi,
-            }));
-        });
+        let res = SelectClients::find_by_statement(Self::list_clients_statement(&args)).all(db).await?;
+        let result: Vec<JsonValue> = res.into_iter().map(Self::list_clients_row).collect();
         
         Ok(json!({
             "count": count,
             "clients": result
         }))
     }
+    pub async fn get_client(db: &DbConn, id: String) -> Result<JsonValue, DbErr> {
+        let client = Clients::find_by_id(id).one(db).await?;
+        
+        match client {
+            Some(client) => Ok(json!({
+                "id": client.id,
+                "fullname": client.full_name,
+                "address": client.address,
+                "image": client.image,
+                "email": client.email,
+                "phoneNumber": client.phone_number,
+                "createdAt": client.created_at,
+            })),
+            None => Err(DbErr::RecordNotFound(String::from("no client"))),
+        }
+    }
     pub async fn search_clients(db: &DbConn, search: String) -> Result<Vec<JsonValue>, DbErr> {
         let clients = Clients::find().select_only().expr_as_(Expr::col(clients::Column::FullName), "label").expr_as_(Expr::col(clients::Column::Id), "value").filter(clients::Column::FullName.like(format!("{}%", search))).into_json().all(db).await?;
         
         Ok(clients)
     }
     //
-    pub async fn list_suppliers(db: &DbConn, args: ListArgs) -> Result<JsonValue, DbErr> {
-        let count = Suppliers::find().filter(suppliers::Column::FullName.like(format!("{}%", args.search))).count(db).await?;
-        
+    pub fn list_suppliers_statement(args: &ListArgs) -> Statement {
         let (sql, values) = Query::select().from(Suppliers).exprs([
             Expr::col((Suppliers, suppliers::Column::Id)),
             Expr::col((Suppliers, suppliers::Column::FullName)),
@@ -228,22 +255,25 @@ impl QueriesService {
             Alias::new("credi"),
         ).cond_where(
             Expr::col((Suppliers, suppliers::Column::FullName)).like(format!("{}%", args.search)).into_condition(),
-        ).limit(args.limit).offset((args.page - 1) * args.limit).order_by(suppliers::Column::CreatedAt, Order::Desc).to_owned().build(SqliteQueryBuilder);
-        
-        let res = SelectSuppliers::find_by_statement(Statement::from_sql_and_values(DbBackend::Sqlite, sql, values)).all(db).await?;
+        ).limit(args.limit()).offset(args.offset()).order_by(suppliers::Column::CreatedAt, Order::Desc).to_owned().build(SqliteQueryBuilder);
+        Statement::from_sql_and_values(DbBackend::Sqlite, sql, values)
+    }
+    pub fn list_suppliers_row(row: SelectSuppliers) -> JsonValue {
+        json!({
+            "id": row.id,
+            "fullname": row.full_name,
+            "address": row.address,
+            "image": row.image,
+            "email": row.email,
+            "phoneNumber": row.phone_number,
+            "credi": row.credi,
+        })
+    }
+    pub async fn list_suppliers(db: &DbConn, args: ListArgs) -> Result<JsonValue, DbErr> {
+        let count = Suppliers::find().filter(suppliers::Column::FullName.like(format!("{}%", args.search))).count(db).await?;
         
-        let mut result = Vec::<JsonValue>::new();
-        res.into_iter().for_each(|row| {
-            result.push(json!({
-                "id": row.id,
-                "fullname": row.full_name,
-                "address": row.address,
-                "image": row.image,
-                "email": row.email,
-                "phoneNumber": row.phone_number,
-                "credi": row.credi,
-            }));
-        });
+        let res = SelectSuppliers::find_by_statement(Self::list_suppliers_statement(&args)).all(db).await?;
+        let result: Vec<JsonValue> = res.into_iter().map(Self::list_suppliers_row).collect();
         
         Ok(json!({
             "count": count,
@@ -256,15 +286,7 @@ impl QueriesService {
         Ok(suppliers)
     }
     //
-    pub async fn list_orders(db: &DbConn, args: ListArgs) -> Result<JsonValue, DbErr> {
-        let count = Orders::find().apply_if(Some(args.search.clone()), |query, v| {
-            query.filter(Expr::col((Clients, clients::Column::FullName)).like(format!("{}%", v)))
-        }).apply_if(args.status.clone(), |query, v| {
-            query.filter(Expr::col((Orders, orders::Column::Status)).eq(v))
-        }).apply_if(args.created_at.clone(), |query, v| {
-            query.filter(Expr::cust_with_values("strftime('%Y-%m-%d', orders.created_at) = ?", [v]))
-        }).join(JoinType::Join, orders::Relation::Clients.def()).count(db).await?;
-        
+    pub fn list_orders_statement(args: &ListArgs) -> Statement {
         let (sql, values) = Query::select().from(Orders).exprs([
             Expr::col((Orders, orders::Column::Id)),
             Expr::col((Orders, orders::Column::Status)),
@@ -298,31 +320,40 @@ impl QueriesService {
         ).cond_where(Expr::col((Clients, clients::Column::FullName)).like(format!("{}%", args.search))).conditions(
             args.status.clone().is_some(),
             |x| {
-                x.and_where(Expr::col((Orders, orders::Column::Status)).eq(args.status));
+                x.and_where(Expr::col((Orders, orders::Column::Status)).eq(args.status.clone()));
             },
             |_| {},
         ).conditions(
             args.created_at.clone().is_some(),
             |x| {
-                x.and_where(Expr::cust_with_values("strftime('%Y-%m-%d', orders.created_at) = ?", args.created_at));
+                x.and_where(Expr::cust_with_values("strftime('%Y-%m-%d', orders.created_at) = ?", args.created_at.clone()));
             },
             |_| {},
-        ).limit(args.limit).offset((args.page - 1) * args.limit).order_by((Orders, orders::Column::CreatedAt), Order::Desc).group_by_col((Orders, orders::Column::Id)).to_owned().build(SqliteQueryBuilder);
-        
-        let res = SelectOrders::find_by_statement(Statement::from_sql_and_values(DbBackend::Sqlite, sql, values)).all(db).await?;
+        ).limit(args.limit()).offset(args.offset()).order_by((Orders, orders::Column::CreatedAt), Order::Desc).group_by_col((Orders, orders::Column::Id)).to_owned().build(SqliteQueryBuilder);
+        Statement::from_sql_and_values(DbBackend::Sqlite, sql, values)
+    }
+    pub fn list_orders_row(row: SelectOrders) -> JsonValue {
+        json!({
+            "id": row.id,
+            "clientId": row.client_id,
+            "createdAt": row.created_at,
+            "fullname": row.full_name,
+            "status": row.status,
+            "products": row.products,
+            "total": row.total,
+        })
+    }
+    pub async fn list_orders(db: &DbConn, args: ListArgs) -> Result<JsonValue, DbErr> {
+        let count = Orders::find().apply_if(Some(args.search.clone()), |query, v| {
+            query.filter(Expr::col((Clients, clients::Column::FullName)).like(format!("{}%", v)))
+        }).apply_if(args.status.clone(), |query, v| {
+            query.filter(Expr::col((Orders, orders::Column::Status)).eq(v))
+        }).apply_if(args.created_at.clone(), |query, v| {
+            query.filter(Expr::cust_with_values("strftime('%Y-%m-%d', orders.created_at) = ?", [v]))
+        }).join(JoinType::Join, orders::Relation::Clients.def()).count(db).await?;
         
-        let mut result = Vec::<JsonValue>::new();
-        res.into_iter().for_each(|row| {
-            result.push(json!({
-                "id": row.id,
-                "clientId": row.client_id,
-                "createdAt": row.created_at,
-                "fullname": row.full_name,
-                "status": row.status,
-                "products": row.products,
-                "total": row.total,
-            }));
-        });
+        let res = SelectOrders::find_by_statement(Self::list_orders_statement(&args)).all(db).await?;
+        let result: Vec<JsonValue> = res.into_iter().map(Self::list_orders_row).collect();
         
         Ok(json!({
             "count": count,
@@ -460,15 +491,7 @@ impl QueriesService {
         }
     }
     //
-    pub async fn list_invoices(db: &DbConn, args: ListArgs) -> Result<JsonValue, DbErr> {
-        let count = Invoices::find().apply_if(Some(args.search.clone()), |query, v| {
-            query.filter(Expr::col((Clients, clients::Column::FullName)).like(format!("{}%", v)))
-        }).apply_if(args.status.clone(), |query, v| {
-            query.filter(Expr::col((Invoices, invoices::Column::Status)).eq(v))
-        }).apply_if(args.created_at.clone(), |query, v| {
-            query.filter(Expr::cust_with_values("strftime('%Y-%m-%d', invoices.created_at) = ?", [v]))
-        }).join(JoinType::Join, invoices::Relation::Clients.def()).count(db).await?;
-        
+    pub fn list_invoices_statement(args: &ListArgs) -> Statement {
         let (sql, values) = Query::select().from(Invoices).exprs([
             Expr::col((Invoices, invoices::Column::Id)),
             Expr::col((Invoices, invoices::Column::Status)),
@@ -503,32 +526,41 @@ impl QueriesService {
         ).cond_where(Expr::col((Clients, clients::Column::FullName)).like(format!("{}%", args.search))).conditions(
             args.status.clone().is_some(),
             |x| {
-                x.and_where(Expr::col((Invoices, invoices::Column::Status)).eq(args.status));
+                x.and_where(Expr::col((Invoices, invoices::Column::Status)).eq(args.status.clone()));
             },
             |_| {},
         ).conditions(
             args.created_at.clone().is_some(),
             |x| {
-                x.and_where(Expr::cust_with_values("strftime('%Y-%m-%d', invoices.created_at) = ?", args.created_at));
+                x.and_where(Expr::cust_with_values("strftime('%Y-%m-%d', invoices.created_at) = ?", args.created_at.clone()));
             },
             |_| {},
-        ).limit(args.limit).offset((args.page - 1) * args.limit).order_by((Invoices, invoices::Column::CreatedAt), Order::Desc).group_by_col((Invoices, invoices::Column::Id)).to_owned().build(SqliteQueryBuilder);
-        
-        let res = SelectInvoices::find_by_statement(Statement::from_sql_and_values(DbBackend::Sqlite, sql, values)).all(db).await?;
+        ).limit(args.limit()).offset(args.offset()).order_by((Invoices, invoices::Column::CreatedAt), Order::Desc).group_by_col((Invoices, invoices::Column::Id)).to_owned().build(SqliteQueryBuilder);
+        Statement::from_sql_and_values(DbBackend::Sqlite, sql, values)
+    }
+    pub fn list_invoices_row(row: SelectInvoices) -> JsonValue {
+        json!({
+            "id": row.id,
+            "clientId": row.client_id,
+            "paidAmount": row.paid_amount,
+            "createdAt": row.created_at,
+            "fullname": row.full_name,
+            "status": row.status,
+            "products": row.products,
+            "total": row.total,
+        })
+    }
+    pub async fn list_invoices(db: &DbConn, args: ListArgs) -> Result<JsonValue, DbErr> {
+        let count = Invoices::find().apply_if(Some(args.search.clone()), |query, v| {
+            query.filter(Expr::col((Clients, clients::Column::FullName)).like(format!("{}%", v)))
+        }).apply_if(args.status.clone(), |query, v| {
+            query.filter(Expr::col((Invoices, invoices::Column::Status)).eq(v))
+        }).apply_if(args.created_at.clone(), |query, v| {
+            query.filter(Expr::cust_with_values("strftime('%Y-%m-%d', invoices.created_at) = ?", [v]))
+        }).join(JoinType::Join, invoices::Relation::Clients.def()).count(db).await?;
         
-        let mut result = Vec::<JsonValue>::new();
-        res.into_iter().for_each(|row| {
-            result.push(json!({
-                "id": row.id,
-                "clientId": row.client_id,
-                "paidAmount": row.paid_amount,
-                "createdAt": row.created_at,
-                "fullname": row.full_name,
-                "status": row.status,
-                "products": row.products,
-                "total": row.total,
-            }));
-        });
+        let res = SelectInvoices::find_by_statement(Self::list_invoices_statement(&args)).all(db).await?;
+        let result: Vec<JsonValue> = res.into_iter().map(Self::list_invoices_row).collect();
         
         Ok(json!({
             "count": count,
@@ -669,13 +701,7 @@ impl QueriesService {
         }
     }
     //
-    pub async fn list_quotes(db: &DbConn, args: ListArgs) -> Result<JsonValue, DbErr> {
-        let count = Quotes::find().apply_if(Some(args.search.clone()), |query, v| {
-            query.filter(Expr::col((Clients, clients::Column::FullName)).like(format!("{}%", v)))
-        }).apply_if(args.created_at.clone(), |query, v| {
-            query.filter(Expr::cust_with_values("strftime('%Y-%m-%d', quotes.created_at) = ?", [v]))
-        }).join(JoinType::Join, quotes::Relation::Clients.def()).count(db).await?;
-        
+    pub fn list_quotes_statement(args: &ListArgs) -> Statement {
         let (sql, values) = Query::select().from(Quotes).exprs([
             Expr::col((Quotes, quotes::Column::Id)),
             Expr::col((Quotes, quotes::Column::CreatedAt)),
@@ -703,24 +729,31 @@ impl QueriesService {
         ).cond_where(Expr::col((Clients, clients::Column::FullName)).like(format!("{}%", args.search))).conditions(
             args.created_at.clone().is_some(),
             |x| {
-                x.and_where(Expr::cust_with_values("strftime('%Y-%m-%d', quotes.created_at) = ?", args.created_at));
+                x.and_where(Expr::cust_with_values("strftime('%Y-%m-%d', quotes.created_at) = ?", args.created_at.clone()));
             },
             |_| {},
-        ).limit(args.limit).offset((args.page - 1) * args.limit).order_by((Quotes, quotes::Column::CreatedAt), Order::Desc).group_by_col((Quotes, quotes::Column::Id)).to_owned().build(SqliteQueryBuilder);
-        
-        let res = SelectQuotes::find_by_statement(Statement::from_sql_and_values(DbBackend::Sqlite, sql, values)).all(db).await?;
+        ).limit(args.limit()).offset(args.offset()).order_by((Quotes, quotes::Column::CreatedAt), Order::Desc).group_by_col((Quotes, quotes::Column::Id)).to_owned().build(SqliteQueryBuilder);
+        Statement::from_sql_and_values(DbBackend::Sqlite, sql, values)
+    }
+    pub fn list_quotes_row(row: SelectQuotes) -> JsonValue {
+        json!({
+            "id": row.id,
+            "clientId": row.client_id,
+            "createdAt": row.created_at,
+            "fullname": row.full_name,
+            "products": row.products,
+            "total": row.total,
+        })
+    }
+    pub async fn list_quotes(db: &DbConn, args: ListArgs) -> Result<JsonValue, DbErr> {
+        let count = Quotes::find().apply_if(Some(args.search.clone()), |query, v| {
+            query.filter(Expr::col((Clients, clients::Column::FullName)).like(format!("{}%", v)))
+        }).apply_if(args.created_at.clone(), |query, v| {
+            query.filter(Expr::cust_with_values("strftime('%Y-%m-%d', quotes.created_at) = ?", [v]))
+        }).join(JoinType::Join, quotes::Relation::Clients.def()).count(db).await?;
         
-        let mut result = Vec::<JsonValue>::new();
-        res.into_iter().for_each(|row| {
-            result.push(json!({
-                "id": row.id,
-                "clientId": row.client_id,
-                "createdAt": row.created_at,
-                "fullname": row.full_name,
-                "products": row.products,
-                "total": row.total,
-            }));
-        });
+        let res = SelectQuotes::find_by_statement(Self::list_quotes_statement(&args)).all(db).await?;
+        let result: Vec<JsonValue> = res.into_iter().map(Self::list_quotes_row).collect();
         
         Ok(json!({
             "count": count,
@@ -840,23 +873,7 @@ impl QueriesService {
         }
     }
     //
-    pub async fn list_inventory(db: &DbConn, args: ListArgs) -> Result<JsonValue, DbErr> {
-        let count = InventoryMouvements::find().join(JoinType::Join, inventory_mouvements::Relation::Products.def()).join(JoinType::LeftJoin, inventory_mouvements::Relation::OrderItems.def()).join(JoinType::LeftJoin, inventory_mouvements::Relation::InvoiceItems.def()).join(JoinType::LeftJoin, invoice_items::Relation::Invoices.def()).join(JoinType::LeftJoin, order_items::Relation::Orders.def()).filter(
-            Cond::all().add(Expr::col((Invoices, invoices::Column::OrderId)).is_null()).add(
-                Expr::expr(Func::coalesce([
-                    Expr::col((Invoices, invoices::Column::Status)).into(),
-                    Expr::col((Orders, orders::Column::Status)).into(),
-                    Expr::expr("PENDING").into(),
-                ])).eq("CANCELED").not(),
-            ),
-        ).apply_if(Some(args.search.clone()), |query, v| {
-            query.filter(Expr::col((Products, products::Column::Name)).like(format!("{}%", v)))
-        }).apply_if(args.status.clone(), |query, v| {
-            query.filter(Expr::col((InventoryMouvements, inventory_mouvements::Column::MvmType)).eq(v))
-        }).apply_if(args.created_at.clone(), |query, v| {
-            query.filter(Expr::cust_with_values("strftime('%Y-%m-%d', inventory_mouvements.created_at) = ?", [v]))
-        }).count(db).await?;
-        //
+    pub fn list_inventory_statement(args: &ListArgs) -> Statement {
         let (sql, values) = Query::select().from(InventoryMouvements).exprs([
             Expr::col((InventoryMouvements, inventory_mouvements::Column::Id)),
             Expr::col((InventoryMouvements, inventory_mouvements::Column::Quantity)),
@@ -901,7 +918,7 @@ impl QueriesService {
         ).and_where(Expr::col((Products, products::Column::Name)).like(format!("{}%", args.search))).conditions(
             args.status.clone().is_some(),
             |x| {
-                x.and_where(Expr::col((InventoryMouvements, inventory_mouvements::Column::MvmType)).eq(args.status));
+                x.and_where(Expr::col((InventoryMouvements, inventory_mouvements::Column::MvmType)).eq(args.status.clone()));
             },
             |_| {},
         ).conditions(
@@ -909,28 +926,45 @@ impl QueriesService {
             |x| {
                 x.and_where(Expr::cust_with_values(
                     "strftime('%Y-%m-%d', inventory_mouvements.created_at) = ?",
-                    args.created_at,
+                    args.created_at.clone(),
                 ));
             },
             |_| {},
         ).order_by_expr(
             Expr::col((InventoryMouvements, inventory_mouvements::Column::CreatedAt)).into(),
             Order::Desc,
-        ).limit(args.limit).offset((args.page - 1) * args.limit).to_owned().build(SqliteQueryBuilder);
+        ).limit(args.limit()).offset(args.offset()).to_owned().build(SqliteQueryBuilder);
+        Statement::from_sql_and_values(DbBackend::Sqlite, sql, values)
+    }
+    pub fn list_inventory_row(row: SelectInventory) -> JsonValue {
+        json!({
+            "id": row.id,
+            "name": row.name,
+            "price": row.price,
+            "createdAt": row.created_at,
+            "quantity": row.quantity,
+            "mvmType": row.mvm_type,
+        })
+    }
+    pub async fn list_inventory(db: &DbConn, args: ListArgs) -> Result<JsonValue, DbErr> {
+        let count = InventoryMouvements::find().join(JoinType::Join, inventory_mouvements::Relation::Products.def()).join(JoinType::LeftJoin, inventory_mouvements::Relation::OrderItems.def()).join(JoinType::LeftJoin, inventory_mouvements::Relation::InvoiceItems.def()).join(JoinType::LeftJoin, invoice_items::Relation::Invoices.def()).join(JoinType::LeftJoin, order_items::Relation::Orders.def()).filter(
+            Cond::all().add(Expr::col((Invoices, invoices::Column::OrderId)).is_null()).add(
+                Expr::expr(Func::coalesce([
+                    Expr::col((Invoices, invoices::Column::Status)).into(),
+                    Expr::col((Orders, orders::Column::Status)).into(),
+                    Expr::expr("PENDING").into(),
+                ])).eq("CANCELED").not(),
+            ),
+        ).apply_if(Some(args.search.clone()), |query, v| {
+            query.filter(Expr::col((Products, products::Column::Name)).like(format!("{}%", v)))
+        }).apply_if(args.status.clone(), |query, v| {
+            query.filter(Expr::col((InventoryMouvements, inventory_mouvements::Column::MvmType)).eq(v))
+        }).apply_if(args.created_at.clone(), |query, v| {
+            query.filter(Expr::cust_with_values("strftime('%Y-%m-%d', inventory_mouvements.created_at) = ?", [v]))
+        }).count(db).await?;
         //
-        let res = SelectInventory::find_by_statement(Statement::from_sql_and_values(DbBackend::Sqlite, sql, values)).all(db).await?;
-        
-        let mut result = Vec::<JsonValue>::new();
-        res.into_iter().for_each(|row| {
-            result.push(json!({
-                "id": row.id,
-                "name": row.name,
-                "price": row.price,
-                "createdAt": row.created_at,
-                "quantity": row.quantity,
-                "mvmType": row.mvm_type,
-            }));
-        });
+        let res = SelectInventory::find_by_statement(Self::list_inventory_statement(&args)).all(db).await?;
+        let result: Vec<JsonValue> = res.into_iter().map(Self::list_inventory_row).collect();
         
         Ok(json!({
             "count": count,
@@ -1245,4 +1279,74 @@ impl QueriesService {
             })).collect::<Vec<JsonValue>>()
         }))
     }
+
+    pub async fn get_vault(db: &DbConn) -> Result<Option<VaultParams>, DbErr> {
+        let vault = Vault::find_by_id(VAULT_ID).one(db).await?;
+        Ok(vault.map(|vault| VaultParams {
+            salt: vault.salt,
+            memory_cost: vault.memory_cost as u32,
+            time_cost: vault.time_cost as u32,
+            parallelism: vault.parallelism as u32,
+            verifier: vault.verifier,
+        }))
+    }
+    pub async fn list_credentials(db: &DbConn) -> Result<Vec<SelectCredentials>, DbErr> {
+        Credentials::find()
+            .select_only()
+            .columns([
+                credentials::Column::Id,
+                credentials::Column::Kind,
+                credentials::Column::Account,
+                credentials::Column::CreatedAt,
+                credentials::Column::UpdatedAt,
+            ])
+            .order_by_asc(credentials::Column::Kind)
+            .order_by_asc(credentials::Column::Account)
+            .into_model::<SelectCredentials>()
+            .all(db)
+            .await
+    }
+    pub async fn get_credential(db: &DbConn, kind: String, account: String) -> Result<Option<Credential>, DbErr> {
+        let credential = Credentials::find()
+            .filter(credentials::Column::Kind.eq(kind))
+            .filter(credentials::Column::Account.eq(account))
+            .one(db)
+            .await?;
+        Ok(credential.map(|c| Credential {
+            id: c.id,
+            kind: c.kind,
+            account: c.account,
+            secret: c.secret,
+        }))
+    }
+    // 更换口令时需要取出全部密文重新加密
+    pub async fn list_credential_secrets(db: &DbConn) -> Result<Vec<Credential>, DbErr> {
+        let credentials = Credentials::find().all(db).await?;
+        Ok(credentials
+            .into_iter()
+            .map(|c| Credential {
+                id: c.id,
+                kind: c.kind,
+                account: c.account,
+                secret: c.secret,
+            })
+            .collect())
+    }
+    //
+    pub async fn list_client_identities(db: &DbConn) -> Result<Vec<ContactIdentity>, DbErr> {
+        Clients::find()
+            .select_only()
+            .columns([clients::Column::Id, clients::Column::FullName, clients::Column::PhoneNumber, clients::Column::Email])
+            .into_model::<ContactIdentity>()
+            .all(db)
+            .await
+    }
+    pub async fn list_supplier_identities(db: &DbConn) -> Result<Vec<ContactIdentity>, DbErr> {
+        Suppliers::find()
+            .select_only()
+            .columns([suppliers::Column::Id, suppliers::Column::FullName, suppliers::Column::PhoneNumber, suppliers::Column::Email])
+            .into_model::<ContactIdentity>()
+            .all(db)
+            .await
+    }
 }
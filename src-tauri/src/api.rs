@@ -1,153 +1,823 @@
-use serde::{Serialize};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use tauri::State;
-use reqwest::{Client, Method};
-use reqwest::header::{HeaderMap, HeaderValue, HeaderName};
+use tauri::{AppHandle, State};
+use reqwest::{Client, Method, RequestBuilder, Response, StatusCode};
+use reqwest::header::{HeaderMap, HeaderValue, HeaderName, AUTHORIZATION, CONTENT_TYPE};
+use reqwest::multipart::{Form, Part};
 use std::collections::HashMap;
-use crate::AppState;
-use log::{info, error};
+use std::path::Path;
+use tokio::io::AsyncWriteExt;
+use crate::commands::{SResult, Seccess};
+use crate::error::AppError;
+use crate::events::EventSink;
+use crate::http::{HttpState, RetryPolicy};
+use crate::redact::LogRedaction;
+use crate::session::SessionState;
+use crate::signing::{self, param_value, SigningKeys, SigningState};
+use log::{info, error, warn};
 
-// 定义通用请求的成功和失败响应结构
-#[derive(Serialize)]
-pub struct SResult<T> {
-    error: Option<String>,
-    message: Option<String>,
-    data: Option<T>,
+// 请求体，以 `type` 字段区分编码方式，Content-Type 由请求体决定
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum RequestBody {
+    // application/x-www-form-urlencoded，非字符串的值会被转成字符串
+    Form { fields: HashMap<String, Value> },
+    // application/json，支持任意嵌套结构
+    Json { value: Value },
+    // multipart/form-data，文件从磁盘读取
+    Multipart { parts: Vec<MultipartPart> },
+    // 原始字节，需要调用方指定 Content-Type
+    Raw { content_type: String, data: Vec<u8> },
 }
 
-#[derive(Serialize)]
-pub struct Fail {
-    error: Option<String>,
-    message: Option<String>,
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum MultipartPart {
+    Text {
+        name: String,
+        value: String,
+    },
+    File {
+        name: String,
+        path: String,
+        // 默认使用路径中的文件名
+        file_name: Option<String>,
+        mime: Option<String>,
+    },
 }
 
-// 定义通用 API 请求方法
-#[tauri::command]
-pub async fn api_request(
-    _state: State<'_, AppState>,
-    method_str: String,
+// 响应信封：状态码、响应头和按 Content-Type 解码后的响应体
+#[derive(Debug, Serialize)]
+pub struct ApiResponse {
+    pub status: u16,
+    pub headers: HashMap<String, String>,
+    pub body: ResponseBody,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ResponseBody {
+    Json { value: Value },
+    Text { text: String },
+    // 二进制内容以 base64 返回
+    Bytes { base64: String },
+    // 已直接写入磁盘的下载
+    File { path: String, size: u64 },
+    Empty,
+}
+
+// 按 HTTP 状态码归类后端返回的错误
+fn status_error(status_code: StatusCode, body: &str) -> AppError {
+    let message = format!("Status code: {} - {}", status_code, body);
+    match status_code.as_u16() {
+        401 | 403 => AppError::Auth(message),
+        404 => AppError::NotFound(message),
+        409 => AppError::Conflict(message),
+        400 | 422 => AppError::Validation(message),
+        _ => AppError::Network(message),
+    }
+}
+
+// 请求体和响应体的日志形式：敏感字段已遮盖，二进制内容只记录长度
+fn describe_request_body(redaction: &LogRedaction, body: &RequestBody) -> String {
+    match body {
+        RequestBody::Form { fields } => redaction.json(&serde_json::to_value(fields).unwrap_or_default()),
+        RequestBody::Json { value } => redaction.json(value),
+        RequestBody::Multipart { parts } => {
+            let parts = parts
+                .iter()
+                .map(|part| match part {
+                    MultipartPart::Text { name, .. } if redaction.is_sensitive(name) => format!("{}=***", name),
+                    MultipartPart::Text { name, value } => format!("{}={}", name, redaction.text(value)),
+                    MultipartPart::File { name, path, .. } => format!("{}=@{}", name, path),
+                })
+                .collect::<Vec<_>>();
+            format!("multipart [{}]", parts.join(", "))
+        }
+        RequestBody::Raw { content_type, data } => format!("{} ({} bytes)", content_type, data.len()),
+    }
+}
+
+fn describe_response_body(redaction: &LogRedaction, body: &ResponseBody) -> String {
+    match body {
+        ResponseBody::Json { value } => redaction.json(value),
+        ResponseBody::Text { text } => redaction.text(text),
+        ResponseBody::Bytes { base64 } => format!("binary ({} base64 chars)", base64.len()),
+        ResponseBody::File { path, size } => format!("file {} ({} bytes)", path, size),
+        ResponseBody::Empty => "empty".to_string(),
+    }
+}
+
+async fn multipart_form(parts: Vec<MultipartPart>) -> Result<Form, AppError> {
+    let mut form = Form::new();
+    for part in parts {
+        form = match part {
+            MultipartPart::Text { name, value } => form.text(name, value),
+            MultipartPart::File { name, path, file_name, mime } => {
+                let bytes = tokio::fs::read(&path)
+                    .await
+                    .map_err(|e| AppError::Validation(format!("Failed to read file {}: {}", path, e)))?;
+                let file_name = file_name.unwrap_or_else(|| {
+                    Path::new(&path)
+                        .file_name()
+                        .map(|name| name.to_string_lossy().into_owned())
+                        .unwrap_or_else(|| name.clone())
+                });
+                let mut file_part = Part::bytes(bytes).file_name(file_name);
+                if let Some(mime) = mime {
+                    file_part = file_part
+                        .mime_str(&mime)
+                        .map_err(|e| AppError::Validation(format!("Invalid mime type {}: {}", mime, e)))?;
+                }
+                form.part(name, file_part)
+            }
+        };
+    }
+    Ok(form)
+}
+
+async fn attach_body(request: RequestBuilder, body: RequestBody) -> Result<RequestBuilder, AppError> {
+    match body {
+        RequestBody::Form { fields } => {
+            let fields: HashMap<String, String> = fields
+                .into_iter()
+                .map(|(key, value)| (key, param_value(value)))
+                .collect();
+            match serde_urlencoded::to_string(&fields) {
+                Ok(form_data) => Ok(request
+                    .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+                    .body(form_data)),
+                Err(err) => {
+                    error!("Failed to encode payload to x-www-form-urlencoded: {}", err);
+                    Err(AppError::Validation("Failed to encode payload".to_string()))
+                }
+            }
+        }
+        RequestBody::Json { value } => Ok(request.json(&value)),
+        RequestBody::Multipart { parts } => Ok(request.multipart(multipart_form(parts).await?)),
+        RequestBody::Raw { content_type, data } => Ok(request.header(CONTENT_TYPE, content_type).body(data)),
+    }
+}
+
+fn parse_method(method_str: &str) -> Result<Method, AppError> {
+    match method_str.to_uppercase().as_str() {
+        "GET" => Ok(Method::GET),
+        "POST" => Ok(Method::POST),
+        "PUT" => Ok(Method::PUT),
+        "PATCH" => Ok(Method::PATCH),
+        "DELETE" => Ok(Method::DELETE),
+        "HEAD" => Ok(Method::HEAD),
+        "OPTIONS" => Ok(Method::OPTIONS),
+        _ => Err(AppError::Validation(format!("Unsupported HTTP method: {}", method_str))),
+    }
+}
+
+fn response_headers(headers: &HeaderMap) -> HashMap<String, String> {
+    let mut result: HashMap<String, String> = HashMap::new();
+    for (name, value) in headers {
+        let Ok(value) = value.to_str() else {
+            continue;
+        };
+        result
+            .entry(name.as_str().to_string())
+            .and_modify(|existing| {
+                existing.push_str(", ");
+                existing.push_str(value);
+            })
+            .or_insert_with(|| value.to_string());
+    }
+    result
+}
+
+fn is_text_type(content_type: &str) -> bool {
+    content_type.starts_with("text/")
+        || content_type.contains("xml")
+        || content_type.contains("javascript")
+        || content_type.contains("x-www-form-urlencoded")
+}
+
+// 按 Content-Type 解码响应体：JSON、文本，其余按二进制返回 base64
+fn decode_body(content_type: Option<&str>, bytes: &[u8]) -> ResponseBody {
+    if bytes.is_empty() {
+        return ResponseBody::Empty;
+    }
+    let content_type = content_type.unwrap_or_default().to_ascii_lowercase();
+    let text = std::str::from_utf8(bytes).ok();
+    if content_type.contains("json") || content_type.is_empty() {
+        if let Ok(value) = serde_json::from_slice::<Value>(bytes) {
+            return ResponseBody::Json { value };
+        }
+    }
+    match text {
+        Some(text) if content_type.is_empty() || content_type.contains("json") || is_text_type(&content_type) => {
+            ResponseBody::Text { text: text.to_string() }
+        }
+        _ => ResponseBody::Bytes {
+            base64: base64::engine::general_purpose::STANDARD.encode(bytes),
+        },
+    }
+}
+
+// 大文件直接分块写入磁盘，不经过 webview
+async fn download_to_file(http: &HttpState, mut response: Response, path: &str) -> Result<u64, AppError> {
+    let io_error = |e: std::io::Error| AppError::Validation(format!("Failed to write {}: {}", path, e));
+    let mut file = tokio::fs::File::create(path).await.map_err(io_error)?;
+    let mut size = 0u64;
+    while let Some(chunk) = http.with_read_timeout(response.chunk()).await? {
+        file.write_all(&chunk).await.map_err(io_error)?;
+        size += chunk.len() as u64;
+    }
+    file.flush().await.map_err(io_error)?;
+    Ok(size)
+}
+
+const IDEMPOTENCY_KEY: &str = "Idempotency-Key";
+
+fn bearer(token: &str) -> Result<HeaderValue, AppError> {
+    HeaderValue::from_str(&format!("Bearer {}", token))
+        .map_err(|_| AppError::Auth("Invalid session token".to_string()))
+}
+
+// 追加 ts 和 sn 签名：有请求体时签在表单或 JSON 对象上，否则签在查询参数上
+fn sign_request(
+    keys: &SigningKeys,
+    method: &Method,
+    query: &mut Option<HashMap<String, String>>,
+    body: &mut Option<RequestBody>,
+) -> Result<(), AppError> {
+    let ts = signing::timestamp();
+    let has_body = matches!(*method, Method::POST | Method::PUT | Method::PATCH) && body.is_some();
+    if !has_body {
+        let query = query.get_or_insert_with(HashMap::new);
+        let mut params = query.clone().into_iter().collect();
+        keys.sign_params(&mut params, ts);
+        *query = params.into_iter().collect();
+        return Ok(());
+    }
+    match body {
+        Some(RequestBody::Form { fields }) => {
+            let mut object = fields.clone().into_iter().collect();
+            keys.sign_object(&mut object, ts);
+            *fields = object.into_iter().collect();
+            Ok(())
+        }
+        Some(RequestBody::Json { value: Value::Object(object) }) => {
+            keys.sign_object(object, ts);
+            Ok(())
+        }
+        _ => Err(AppError::Validation(
+            "Only query, form and JSON object requests can be signed".to_string(),
+        )),
+    }
+}
+
+// 发送前的请求参数，每次尝试都重新构建请求：multipart 请求体无法复用，需要重新读取文件
+struct PreparedRequest {
+    client: Client,
+    method: Method,
     url: String,
-    payload: Option<HashMap<String, String>>, 
-    headers: Option<HashMap<String, String>>, 
-) -> Result<SResult<Value>, Fail> {
+    query: Option<HashMap<String, String>>,
+    headers: HeaderMap,
+    body: Option<RequestBody>,
+}
+
+impl PreparedRequest {
+    async fn build(&self) -> Result<RequestBuilder, AppError> {
+        let mut request = self.client.request(self.method.clone(), &self.url).headers(self.headers.clone());
+        // 查询参数单独编码，不再拼接到 URL 中
+        if let Some(query) = &self.query {
+            request = request.query(query);
+        }
+        // 添加请求体（仅针对 POST、PUT 和 PATCH 方法）
+        if matches!(self.method, Method::POST | Method::PUT | Method::PATCH) {
+            if let Some(body) = &self.body {
+                request = attach_body(request, body.clone()).await?;
+            }
+        }
+        Ok(request)
+    }
+}
+
+// 按重试策略发送请求，网络错误和可重试的状态码会在退避后重试
+async fn send_with_retry(
+    http: &HttpState,
+    policy: &RetryPolicy,
+    retryable: bool,
+    prepared: &PreparedRequest,
+) -> Result<Response, AppError> {
+    let max_attempts = if retryable { policy.max_attempts.max(1) } else { 1 };
+    let redaction = http.settings().log_redaction;
+    let mut attempt = 1;
+    loop {
+        let request = prepared.build().await?;
+        let delay = match http.with_read_timeout(request.send()).await {
+            Ok(response) if attempt < max_attempts && policy.should_retry_status(response.status()) => {
+                let delay = policy.delay(attempt, Some(response.headers()));
+                warn!(
+                    "Attempt {}/{} returned {}, retrying in {} ms",
+                    attempt,
+                    max_attempts,
+                    response.status(),
+                    delay.as_millis()
+                );
+                delay
+            }
+            Ok(response) => {
+                info!("Attempt {}/{} returned {}", attempt, max_attempts, response.status());
+                return Ok(response);
+            }
+            Err(err) if attempt < max_attempts => {
+                let delay = policy.delay(attempt, None);
+                warn!(
+                    "Attempt {}/{} failed: {}, retrying in {} ms",
+                    attempt,
+                    max_attempts,
+                    redaction.text(err.message()),
+                    delay.as_millis()
+                );
+                delay
+            }
+            Err(err) => {
+                let error_message = format!("Request failed: {}", err.message());
+                error!("Attempt {}/{} failed: {}", attempt, max_attempts, redaction.text(&error_message));
+                return Err(AppError::Network(error_message));
+            }
+        };
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+// api_request 的参数，与 Tauri State 无关，便于直接测试
+pub struct ApiRequest {
+    pub method: String,
+    pub url: String,
+    pub query: Option<HashMap<String, String>>,
+    pub body: Option<RequestBody>,
+    pub headers: Option<HashMap<String, String>>,
+    pub download_to: Option<String>,
+    pub idempotency_key: Option<String>,
+    pub sign: bool,
+}
+
+// 发送请求的完整流程：签名、附带会话令牌、重试、401 刷新令牌后重发、按 Content-Type 解码响应
+pub async fn send_api_request(
+    events: &dyn EventSink,
+    http: &HttpState,
+    keys: &SigningKeys,
+    session: &SessionState,
+    request: ApiRequest,
+) -> Result<ApiResponse, AppError> {
+    let ApiRequest {
+        method: method_str,
+        url,
+        query,
+        body,
+        headers,
+        download_to,
+        idempotency_key,
+        sign,
+    } = request;
+
+    // 日志中的令牌、密码和签名统一脱敏
+    let redaction = http.settings().log_redaction;
     info!("Request Method: {}", method_str);
-    info!("Request URL: {}", url);
+    info!("Request URL: {}", redaction.text(&url));
     if let Some(headers) = &headers {
-        info!("Request Headers: {:?}", headers);
+        info!("Request Headers: {:?}", redaction.pairs(headers));
     }
-    if let Some(payload) = &payload {
-        info!("Request Payload: {:?}", payload);
+    if let Some(query) = &query {
+        info!("Request Query: {:?}", redaction.pairs(query));
+    }
+    if let Some(body) = &body {
+        info!("Request Body: {}", describe_request_body(&redaction, body));
     }
-
-    // 创建请求客户端
-    let client = Client::new();
 
     // 将字符串转换为对应的 HTTP 方法
-    let method = match method_str.to_uppercase().as_str() {
-        "GET" => Method::GET,
-        "POST" => Method::POST,
-        "PUT" => Method::PUT,
-        "DELETE" => Method::DELETE,
-        _ => {
-            return Err(Fail {
-                error: Some(format!("Unsupported HTTP method: {}", method_str)),
-                message: None,
-            });
-        }
-    };
+    let method = parse_method(&method_str)?;
 
-    // 构建请求
-    let mut request = client.request(method.clone(), &url);
+    let mut query = query;
+    let mut body = body;
+    if sign {
+        sign_request(keys, &method, &mut query, &mut body)?;
+    }
 
     // 添加请求头（如果有）
+    let mut req_headers = HeaderMap::new();
     if let Some(headers_map) = headers {
-        let mut req_headers = HeaderMap::new();
         for (key, value) in headers_map {
+            // 有请求体时 Content-Type 由请求体决定，忽略调用方传入的值
+            if body.is_some() && key.eq_ignore_ascii_case(CONTENT_TYPE.as_str()) {
+                continue;
+            }
             if let (Ok(header_name), Ok(header_value)) = (
                 HeaderName::from_bytes(key.as_bytes()),
                 HeaderValue::from_str(&value),
             ) {
                 req_headers.insert(header_name, header_value);
             } else {
-                error!("Invalid header detected: {}: {}", key, value);
+                error!("Invalid header detected: {}", key);
             }
         }
-        request = request.headers(req_headers);
+    }
+    if let Some(key) = &idempotency_key {
+        let value = HeaderValue::from_str(key)
+            .map_err(|_| AppError::Validation(format!("Invalid idempotency key: {}", key)))?;
+        req_headers.insert(IDEMPOTENCY_KEY, value);
     }
 
-    // 添加请求体（仅针对 POST 和 PUT 方法）
-    if matches!(method, Method::POST | Method::PUT) {
-        if let Some(payload_data) = &payload {
-            match serde_urlencoded::to_string(payload_data) {
-                Ok(form_data) => {
-                    info!("Form Data: {}", form_data);
-                    request = request.header("Content-Type", "application/x-www-form-urlencoded")
-                                     .body(form_data);
-                }
-                Err(err) => {
-                    error!("Failed to encode payload to x-www-form-urlencoded: {}", err);
-                    return Err(Fail {
-                        error: Some("Failed to encode payload".to_string()),
-                        message: None,
-                    });
-                }
-            }
+    // 调用方未指定 Authorization 时，为后端请求附带会话令牌
+    let mut session_token = None;
+    if !req_headers.contains_key(AUTHORIZATION) && http.settings().is_backend(&url) {
+        if let Some(token) = session.access_token() {
+            req_headers.insert(AUTHORIZATION, bearer(&token)?);
+            session_token = Some(token);
         }
     }
 
     // 发送请求并处理响应
-    match request.send().await {
-        Ok(response) => {
-            let status_code = response.status();
-            let body = response.text().await.unwrap_or_else(|_| "No response body".to_string());
-
-            // 分类处理响应状态码
-            match status_code.as_u16() {
-                200..=299 => {
-                    match serde_json::from_str::<Value>(&body) {
-                        Ok(data) => {
-                            info!("Request successful with data: {:?}", data);
-                            Ok(SResult {
-                                error: None,
-                                message: Some("Request successful".to_string()),
-                                data: Some(data),
-                            })
-                        }
-                        Err(_) => {
-                            error!("Failed to parse response body as JSON: {}", body);
-                            Err(Fail {
-                                error: Some("Failed to parse response".to_string()),
-                                message: None,
-                            })
-                        }
-                    }
-                }
-                401 => {
-                    error!("Authentication failed (401). Response body: {}", body);
-                    Err(Fail {
-                        error: Some(status_code.to_string()),
-                        message: Some(format!("Status code: {} - {}", status_code, body)),
-                    })
-                }
-                _ => {
-                    error!(
-                        "API request failed with status code: {}, body: {}",
-                        status_code, body
-                    );
-                    Err(Fail {
-                        error: Some("API request failed".to_string()),
-                        message: Some(format!("Status code: {} - {}", status_code, body)),
-                    })
-                }
+    let policy = http.settings().retry;
+    let retryable = policy.applies_to(&method, idempotency_key.is_some());
+    let mut prepared = PreparedRequest {
+        // 使用共享的请求客户端（连接池、代理、超时等由 HTTP 设置决定）
+        client: http.client(),
+        method: method.clone(),
+        url,
+        query,
+        headers: req_headers,
+        body,
+    };
+    let mut response = send_with_retry(http, &policy, retryable, &prepared).await?;
+
+    // 令牌过期：刷新（并发请求共用一次刷新）后重发一次
+    if let (StatusCode::UNAUTHORIZED, Some(stale_token)) = (response.status(), &session_token) {
+        warn!("Request returned 401, refreshing session token");
+        let token = session.refresh(events, http, keys, stale_token).await?;
+        prepared.headers.insert(AUTHORIZATION, bearer(&token)?);
+        response = send_with_retry(http, &policy, retryable, &prepared).await?;
+    }
+
+    let status_code = response.status();
+    let headers = response_headers(response.headers());
+
+    // 分类处理响应状态码
+    if !status_code.is_success() {
+        let body = response.text().await.unwrap_or_else(|_| "No response body".to_string());
+        if status_code == StatusCode::UNAUTHORIZED {
+            error!("Authentication failed (401). Response body: {}", redaction.text(&body));
+        } else {
+            error!(
+                "API request failed with status code: {}, body: {}",
+                status_code,
+                redaction.text(&body)
+            );
+        }
+        return Err(status_error(status_code, &body));
+    }
+
+    let body = match download_to {
+        Some(path) => {
+            let size = download_to_file(http, response, &path).await?;
+            info!("Downloaded {} bytes to {}", size, path);
+            ResponseBody::File { path, size }
+        }
+        None if method == Method::HEAD => ResponseBody::Empty,
+        None => {
+            let content_type = headers.get(CONTENT_TYPE.as_str()).cloned();
+            let bytes = http.with_read_timeout(response.bytes()).await?;
+            decode_body(content_type.as_deref(), &bytes)
+        }
+    };
+    info!(
+        "Request successful with status {}: {}",
+        status_code,
+        describe_response_body(&redaction, &body)
+    );
+
+    Ok(ApiResponse {
+        status: status_code.as_u16(),
+        headers,
+        body,
+    })
+}
+
+// 定义通用 API 请求方法
+// POST/PATCH 只有在传入 idempotency_key 时才会自动重试，该值会作为 Idempotency-Key 请求头发送
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn api_request(
+    app: AppHandle,
+    http: State<'_, HttpState>,
+    signing: State<'_, SigningState>,
+    session: State<'_, SessionState>,
+    method_str: String,
+    url: String,
+    query: Option<HashMap<String, String>>,
+    body: Option<RequestBody>,
+    headers: Option<HashMap<String, String>>,
+    download_to: Option<String>,
+    idempotency_key: Option<String>,
+    sign: Option<bool>, // 由 Rust 端追加 ts 和 sn 签名，密钥不再暴露给前端
+) -> SResult<ApiResponse> {
+    let request = ApiRequest {
+        method: method_str,
+        url,
+        query,
+        body,
+        headers,
+        download_to,
+        idempotency_key,
+        sign: sign.unwrap_or(false),
+    };
+    let response = send_api_request(&app, &http, &signing.keys()?, &session, request).await?;
+    Ok(Seccess {
+        error: None,
+        message: Some("Request successful".to_string()),
+        data: Some(response),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::RecordedEvents;
+    use crate::http::HttpSettings;
+    use crate::session::{SessionTokens, EVENT_EXPIRED};
+    use serde_json::json;
+    use wiremock::matchers::{body_json, body_string_contains, header, header_exists, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    struct Fixture {
+        server: MockServer,
+        events: RecordedEvents,
+        http: HttpState,
+        session: SessionState,
+    }
+
+    impl Fixture {
+        async fn start() -> Self {
+            let server = MockServer::start().await;
+            let http = HttpState::load(None);
+            let mut settings = HttpSettings {
+                base_url: format!("{}/api/", server.uri()),
+                ..Default::default()
+            };
+            settings.retry.initial_backoff_ms = 10;
+            settings.retry.max_backoff_ms = 50;
+            http.update(settings).unwrap();
+            Self {
+                server,
+                events: RecordedEvents::default(),
+                http,
+                session: SessionState::load(None),
             }
         }
-        Err(err) => {
-            let error_message = format!("Request failed: {}", err);
-            error!("{}", error_message);
-            Err(Fail {
-                error: Some(error_message),
-                message: None,
-            })
+
+        fn url(&self, path: &str) -> String {
+            format!("{}/api/{}", self.server.uri(), path)
+        }
+
+        fn login(&self, token: &str, refresh_token: Option<&str>) {
+            self.session.set(Some(SessionTokens {
+                token: token.to_string(),
+                refresh_token: refresh_token.map(str::to_string),
+            }));
+        }
+
+        async fn send(&self, request: ApiRequest) -> Result<ApiResponse, AppError> {
+            send_api_request(&self.events, &self.http, &SigningKeys::default(), &self.session, request).await
         }
     }
+
+    fn request(method: &str, url: String) -> ApiRequest {
+        ApiRequest {
+            method: method.to_string(),
+            url,
+            query: None,
+            body: None,
+            headers: None,
+            download_to: None,
+            idempotency_key: None,
+            sign: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn signs_form_bodies() {
+        let fixture = Fixture::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/ums/mail/group"))
+            .and(header("content-type", "application/x-www-form-urlencoded"))
+            .and(body_string_contains("name=inbox"))
+            .and(body_string_contains("gid=12"))
+            .and(body_string_contains("ts="))
+            .and(body_string_contains("sn="))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "code": 0 })))
+            .expect(1)
+            .mount(&fixture.server)
+            .await;
+
+        let mut req = request("POST", fixture.url("ums/mail/group"));
+        req.body = Some(RequestBody::Form {
+            fields: HashMap::from([("name".to_string(), json!("inbox")), ("gid".to_string(), json!(12))]),
+        });
+        req.sign = true;
+        let res = fixture.send(req).await.unwrap();
+
+        assert_eq!(res.status, 200);
+        assert!(matches!(res.body, ResponseBody::Json { value } if value == json!({ "code": 0 })));
+    }
+
+    #[tokio::test]
+    async fn sends_json_bodies_and_signs_queries() {
+        let fixture = Fixture::start().await;
+        Mock::given(method("PUT"))
+            .and(path("/api/items/1"))
+            .and(query_param("page", "2"))
+            .and(body_json(json!({ "tags": ["a", "b"], "nested": { "n": 1 } })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "ok": true })))
+            .expect(1)
+            .mount(&fixture.server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/items"))
+            .respond_with(|req: &wiremock::Request| {
+                let keys: Vec<String> = req.url.query_pairs().map(|(key, _)| key.into_owned()).collect();
+                ResponseTemplate::new(200).set_body_json(keys)
+            })
+            .expect(1)
+            .mount(&fixture.server)
+            .await;
+
+        let mut put = request("PUT", fixture.url("items/1"));
+        put.query = Some(HashMap::from([("page".to_string(), "2".to_string())]));
+        put.body = Some(RequestBody::Json {
+            value: json!({ "tags": ["a", "b"], "nested": { "n": 1 } }),
+        });
+        fixture.send(put).await.unwrap();
+
+        let mut get = request("GET", fixture.url("items"));
+        get.query = Some(HashMap::from([("page".to_string(), "2".to_string())]));
+        get.sign = true;
+        let res = fixture.send(get).await.unwrap();
+        let ResponseBody::Json { value } = res.body else { panic!("expected JSON") };
+        let mut keys: Vec<&str> = value.as_array().unwrap().iter().filter_map(Value::as_str).collect();
+        keys.sort_unstable();
+        assert_eq!(keys, ["page", "sn", "ts"]);
+    }
+
+    #[tokio::test]
+    async fn decodes_non_json_replies() {
+        let fixture = Fixture::start().await;
+        Mock::given(path("/api/text"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw("plain reply", "text/plain"))
+            .mount(&fixture.server)
+            .await;
+        Mock::given(path("/api/broken-json"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw("{not json", "application/json"))
+            .mount(&fixture.server)
+            .await;
+        Mock::given(path("/api/bytes"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(vec![0u8, 159, 146, 150], "application/octet-stream"))
+            .mount(&fixture.server)
+            .await;
+        Mock::given(path("/api/empty"))
+            .respond_with(ResponseTemplate::new(204))
+            .mount(&fixture.server)
+            .await;
+
+        let text = fixture.send(request("GET", fixture.url("text"))).await.unwrap();
+        assert!(matches!(text.body, ResponseBody::Text { text } if text == "plain reply"));
+        let broken = fixture.send(request("GET", fixture.url("broken-json"))).await.unwrap();
+        assert!(matches!(broken.body, ResponseBody::Text { text } if text == "{not json"));
+        let bytes = fixture.send(request("GET", fixture.url("bytes"))).await.unwrap();
+        assert!(matches!(bytes.body, ResponseBody::Bytes { base64 } if base64 == "AJ+Slg=="));
+        let empty = fixture.send(request("DELETE", fixture.url("empty"))).await.unwrap();
+        assert_eq!(empty.status, 204);
+        assert!(matches!(empty.body, ResponseBody::Empty));
+    }
+
+    #[tokio::test]
+    async fn maps_error_statuses() {
+        let fixture = Fixture::start().await;
+        Mock::given(path("/api/missing"))
+            .respond_with(ResponseTemplate::new(404).set_body_string("no such item"))
+            .mount(&fixture.server)
+            .await;
+        Mock::given(path("/api/invalid"))
+            .respond_with(ResponseTemplate::new(422).set_body_string("bad field"))
+            .mount(&fixture.server)
+            .await;
+
+        let err = fixture.send(request("GET", fixture.url("missing"))).await.unwrap_err();
+        assert_eq!(err.code(), "NOT_FOUND");
+        assert!(err.message().contains("no such item"));
+        let err = fixture.send(request("POST", fixture.url("invalid"))).await.unwrap_err();
+        assert_eq!(err.code(), "VALIDATION");
+    }
+
+    #[tokio::test]
+    async fn retries_idempotent_requests() {
+        let fixture = Fixture::start().await;
+        Mock::given(path("/api/flaky"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .mount(&fixture.server)
+            .await;
+        Mock::given(path("/api/flaky"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "ok": true })))
+            .mount(&fixture.server)
+            .await;
+
+        let res = fixture.send(request("GET", fixture.url("flaky"))).await.unwrap();
+        assert_eq!(res.status, 200);
+
+        // POST 没有幂等键时不重试
+        Mock::given(path("/api/flaky-post"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(1)
+            .mount(&fixture.server)
+            .await;
+        let err = fixture.send(request("POST", fixture.url("flaky-post"))).await.unwrap_err();
+        assert_eq!(err.code(), "NETWORK");
+    }
+
+    #[tokio::test]
+    async fn refreshes_token_once_on_401() {
+        let fixture = Fixture::start().await;
+        fixture.login("old-token", Some("refresh-1"));
+        Mock::given(path("/api/profile"))
+            .and(header("authorization", "Bearer old-token"))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&fixture.server)
+            .await;
+        Mock::given(path("/api/profile"))
+            .and(header("authorization", "Bearer new-token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "name": "admin" })))
+            .mount(&fixture.server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/ums/user/refresh-token"))
+            .and(body_string_contains("refresh_token=refresh-1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "data": { "token": "new-token" } })))
+            .expect(1)
+            .mount(&fixture.server)
+            .await;
+
+        // 两个并发请求同时收到 401，只刷新一次
+        let (first, second) = tokio::join!(
+            fixture.send(request("GET", fixture.url("profile"))),
+            fixture.send(request("GET", fixture.url("profile"))),
+        );
+        assert_eq!(first.unwrap().status, 200);
+        assert_eq!(second.unwrap().status, 200);
+
+        let tokens = fixture.session.tokens().unwrap();
+        assert_eq!(tokens.token, "new-token");
+        assert_eq!(tokens.refresh_token.as_deref(), Some("refresh-1"));
+        assert!(fixture.events.named(EVENT_EXPIRED).is_empty());
+    }
+
+    #[tokio::test]
+    async fn expires_session_when_refresh_is_rejected() {
+        let fixture = Fixture::start().await;
+        fixture.login("old-token", Some("refresh-1"));
+        Mock::given(path("/api/profile"))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&fixture.server)
+            .await;
+        Mock::given(path("/api/ums/user/refresh-token"))
+            .respond_with(ResponseTemplate::new(401).set_body_string("refresh token revoked"))
+            .mount(&fixture.server)
+            .await;
+
+        let err = fixture.send(request("GET", fixture.url("profile"))).await.unwrap_err();
+
+        assert_eq!(err.code(), "AUTH");
+        assert!(fixture.session.tokens().is_none());
+        assert_eq!(fixture.events.named(EVENT_EXPIRED).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn keeps_tokens_away_from_other_hosts() {
+        let fixture = Fixture::start().await;
+        let other = MockServer::start().await;
+        fixture.login("secret-token", None);
+        Mock::given(path("/public"))
+            .and(header_exists("authorization"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "authorized": true })))
+            .with_priority(1)
+            .mount(&other)
+            .await;
+        Mock::given(path("/public"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "authorized": false })))
+            .mount(&other)
+            .await;
+
+        // 另一个 MockServer 同为 127.0.0.1，用 localhost 访问模拟不同的主机
+        let url = other.uri().replace("127.0.0.1", "localhost") + "/public";
+        let res = fixture.send(request("GET", url)).await.unwrap();
+        assert!(matches!(res.body, ResponseBody::Json { value } if value["authorized"] == false));
+    }
 }
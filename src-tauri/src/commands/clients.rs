@@ -1,15 +1,16 @@
 use serde_json::Value;
 use service::{Client, ListArgs, MutationsService, NewClient, QueriesService};
-use tauri::State;
+use tauri::{AppHandle, State};
 
+use crate::events::{self, Entity, Operation};
 use crate::AppState;
 
-use super::{Fail, SResult, Seccess};
+use super::{SResult, Seccess};
 
 #[tauri::command]
 pub async fn list_clients(state: State<'_, AppState>, args: ListArgs) -> SResult<Value> {
-    let _ = state.db_conn;
-    let res = QueriesService::list_clients(&state.db_conn, args).await;
+    let db = state.db()?;
+    let res = QueriesService::list_clients(db, args).await;
     match res {
         Ok(res) => Ok(Seccess {
             error: None,
@@ -18,18 +19,32 @@ pub async fn list_clients(state: State<'_, AppState>, args: ListArgs) -> SResult
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_client(state: State<'_, AppState>, id: String) -> SResult<Value> {
+    let db = state.db()?;
+    let res = QueriesService::get_client(db, id).await;
+    match res {
+        Ok(res) => Ok(Seccess {
+            error: None,
+            message: None,
+            data: Some(res),
+        }),
+        Err(err) => {
+            println!("Error: {}", err);
+            Err(err.into())
         }
     }
 }
 
 #[tauri::command]
 pub async fn search_clients(state: State<'_, AppState>, search: String) -> SResult<Vec<Value>> {
-    let _ = state.db_conn;
-    let res = QueriesService::search_clients(&state.db_conn, search).await;
+    let db = state.db()?;
+    let res = QueriesService::search_clients(db, search).await;
     match res {
         Ok(res) => Ok(Seccess {
             error: None,
@@ -38,18 +53,18 @@ pub async fn search_clients(state: State<'_, AppState>, search: String) -> SResu
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
 
 #[tauri::command]
-pub async fn create_client(state: State<'_, AppState>, client: NewClient) -> SResult<String> {
-    let _ = state.db_conn;
-    let res = MutationsService::create_client(&state.db_conn, client).await;
+pub async fn create_client(app: AppHandle, state: State<'_, AppState>, client: NewClient) -> SResult<String> {
+    let db = state.db()?;
+    let res = MutationsService::create_client(db, client).await;
+    if let Ok(id) = &res {
+        events::db_change(&app, Entity::Clients, Some(id.clone()), Operation::Created);
+    }
     match res {
         Ok(id) => Ok(Seccess::<String> {
             error: None,
@@ -58,18 +73,19 @@ pub async fn create_client(state: State<'_, AppState>, client: NewClient) -> SRe
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
 
 #[tauri::command]
-pub async fn delete_client(state: State<'_, AppState>, id: String) -> SResult<u64> {
-    let _ = state.db_conn;
-    let res = MutationsService::delete_client(&state.db_conn, id).await;
+pub async fn delete_client(app: AppHandle, state: State<'_, AppState>, id: String) -> SResult<u64> {
+    let db = state.db()?;
+    let deleted = id.clone();
+    let res = MutationsService::delete_client(db, id).await;
+    if matches!(res, Ok(rows) if rows > 0) {
+        events::db_change(&app, Entity::Clients, Some(deleted), Operation::Deleted);
+    }
     match res {
         Ok(res) => Ok(Seccess {
             error: None,
@@ -78,18 +94,19 @@ pub async fn delete_client(state: State<'_, AppState>, id: String) -> SResult<u6
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
 
 #[tauri::command]
-pub async fn update_client(state: State<'_, AppState>, client: Client) -> SResult<String> {
-    let _ = state.db_conn;
-    let res = MutationsService::update_client(&state.db_conn, client).await;
+pub async fn update_client(app: AppHandle, state: State<'_, AppState>, client: Client) -> SResult<String> {
+    let db = state.db()?;
+    let updated = client.id.clone();
+    let res = MutationsService::update_client(db, client).await;
+    if res.is_ok() {
+        events::db_change(&app, Entity::Clients, Some(updated), Operation::Updated);
+    }
     match res {
         Ok(_) => Ok(Seccess::<String> {
             error: None,
@@ -98,10 +115,7 @@ pub async fn update_client(state: State<'_, AppState>, client: Client) -> SResul
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
@@ -4,12 +4,12 @@ use tauri::State;
 
 use crate::AppState;
 
-use super::{Fail, SResult, Seccess};
+use super::{SResult, Seccess};
 
 #[tauri::command]
 pub async fn list_mvm_stats(state: State<'_, AppState>) -> SResult<Vec<Value>> {
-    let _ = state.db_conn;
-    let res = QueriesService::list_mvm_stats(&state.db_conn).await;
+    let db = state.db()?;
+    let res = QueriesService::list_mvm_stats(db).await;
     match res {
         Ok(res) => Ok(Seccess {
             error: None,
@@ -18,18 +18,15 @@ pub async fn list_mvm_stats(state: State<'_, AppState>) -> SResult<Vec<Value>> {
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
 
 #[tauri::command]
 pub async fn list_top_clients(state: State<'_, AppState>) -> SResult<Vec<Value>> {
-    let _ = state.db_conn;
-    let res = QueriesService::list_top_clients(&state.db_conn).await;
+    let db = state.db()?;
+    let res = QueriesService::list_top_clients(db).await;
     match res {
         Ok(res) => Ok(Seccess {
             error: None,
@@ -38,18 +35,15 @@ pub async fn list_top_clients(state: State<'_, AppState>) -> SResult<Vec<Value>>
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
 
 #[tauri::command]
 pub async fn list_top_suppliers(state: State<'_, AppState>) -> SResult<Vec<Value>> {
-    let _ = state.db_conn;
-    let res = QueriesService::list_top_suppliers(&state.db_conn).await;
+    let db = state.db()?;
+    let res = QueriesService::list_top_suppliers(db).await;
     match res {
         Ok(res) => Ok(Seccess {
             error: None,
@@ -58,18 +52,15 @@ pub async fn list_top_suppliers(state: State<'_, AppState>) -> SResult<Vec<Value
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
 
 #[tauri::command]
 pub async fn list_top_products(state: State<'_, AppState>) -> SResult<Vec<Value>> {
-    let _ = state.db_conn;
-    let res = QueriesService::list_top_products(&state.db_conn).await;
+    let db = state.db()?;
+    let res = QueriesService::list_top_products(db).await;
     match res {
         Ok(res) => Ok(Seccess {
             error: None,
@@ -78,18 +69,15 @@ pub async fn list_top_products(state: State<'_, AppState>) -> SResult<Vec<Value>
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
 
 #[tauri::command]
 pub async fn list_status_count(state: State<'_, AppState>) -> SResult<Value> {
-    let _ = state.db_conn;
-    let res = QueriesService::list_status_count(&state.db_conn).await;
+    let db = state.db()?;
+    let res = QueriesService::list_status_count(db).await;
     match res {
         Ok(res) => Ok(Seccess {
             error: None,
@@ -98,18 +86,15 @@ pub async fn list_status_count(state: State<'_, AppState>) -> SResult<Value> {
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
 
 #[tauri::command]
 pub async fn list_revenue(state: State<'_, AppState>) -> SResult<Value> {
-    let _ = state.db_conn;
-    let res = QueriesService::list_revenue(&state.db_conn).await;
+    let db = state.db()?;
+    let res = QueriesService::list_revenue(db).await;
     match res {
         Ok(res) => Ok(Seccess {
             error: None,
@@ -118,18 +103,15 @@ pub async fn list_revenue(state: State<'_, AppState>) -> SResult<Value> {
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
 
 #[tauri::command]
 pub async fn list_expenses(state: State<'_, AppState>) -> SResult<Value> {
-    let _ = state.db_conn;
-    let res = QueriesService::list_expenses(&state.db_conn).await;
+    let db = state.db()?;
+    let res = QueriesService::list_expenses(db).await;
     match res {
         Ok(res) => Ok(Seccess {
             error: None,
@@ -138,10 +120,7 @@ pub async fn list_expenses(state: State<'_, AppState>) -> SResult<Value> {
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
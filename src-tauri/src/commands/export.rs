@@ -0,0 +1,479 @@
+use chrono::NaiveDateTime;
+use futures_util::stream::BoxStream;
+use futures_util::{StreamExt, TryStreamExt};
+use log::{error, info};
+use rust_xlsxwriter::{Format, Workbook, XlsxError};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use service::sea_orm::{DbConn, DbErr, FromQueryResult};
+use service::{
+    ListArgs, QueriesService, SelectClients, SelectInventory, SelectInvoices, SelectOrders, SelectProducts, SelectQuotes,
+    SelectSuppliers,
+};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use tauri::State;
+
+use crate::error::AppError;
+use crate::AppState;
+
+use super::{SResult, Seccess};
+
+// 数据库中 created_at 的格式（SQLite CURRENT_TIMESTAMP）
+const DB_DATETIME: &str = "%Y-%m-%d %H:%M:%S";
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportTable {
+    Products,
+    Clients,
+    Suppliers,
+    Orders,
+    Invoices,
+    Quotes,
+    Inventory,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    Csv,
+    Xlsx,
+}
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Text,
+    Integer,
+    Number,
+    Date,
+}
+
+// 导出列：列表 JSON 中的字段名、表头和格式化方式
+struct Column {
+    key: &'static str,
+    header: &'static str,
+    kind: Kind,
+}
+
+const fn column(key: &'static str, header: &'static str, kind: Kind) -> Column {
+    Column { key, header, kind }
+}
+
+const PRODUCT_COLUMNS: &[Column] = &[
+    column("name", "Name", Kind::Text),
+    column("description", "Description", Kind::Text),
+    column("price", "Price", Kind::Number),
+    column("stock", "Stock", Kind::Number),
+    column("minQuantity", "Min quantity", Kind::Number),
+    column("createdAt", "Created at", Kind::Date),
+];
+
+// 客户与供应商字段相同
+const CONTACT_COLUMNS: &[Column] = &[
+    column("fullname", "Full name", Kind::Text),
+    column("email", "Email", Kind::Text),
+    column("phoneNumber", "Phone number", Kind::Text),
+    column("address", "Address", Kind::Text),
+    column("credi", "Credit", Kind::Number),
+];
+
+const ORDER_COLUMNS: &[Column] = &[
+    column("fullname", "Client", Kind::Text),
+    column("status", "Status", Kind::Text),
+    column("products", "Products", Kind::Integer),
+    column("total", "Total", Kind::Number),
+    column("createdAt", "Created at", Kind::Date),
+];
+
+const INVOICE_COLUMNS: &[Column] = &[
+    column("fullname", "Client", Kind::Text),
+    column("status", "Status", Kind::Text),
+    column("products", "Products", Kind::Integer),
+    column("total", "Total", Kind::Number),
+    column("paidAmount", "Paid amount", Kind::Number),
+    column("createdAt", "Created at", Kind::Date),
+];
+
+const QUOTE_COLUMNS: &[Column] = &[
+    column("fullname", "Client", Kind::Text),
+    column("products", "Products", Kind::Integer),
+    column("total", "Total", Kind::Number),
+    column("createdAt", "Created at", Kind::Date),
+];
+
+const INVENTORY_COLUMNS: &[Column] = &[
+    column("name", "Product", Kind::Text),
+    column("mvmType", "Type", Kind::Text),
+    column("quantity", "Quantity", Kind::Number),
+    column("price", "Price", Kind::Number),
+    column("createdAt", "Created at", Kind::Date),
+];
+
+impl ExportTable {
+    fn columns(self) -> &'static [Column] {
+        match self {
+            Self::Products => PRODUCT_COLUMNS,
+            Self::Clients | Self::Suppliers => CONTACT_COLUMNS,
+            Self::Orders => ORDER_COLUMNS,
+            Self::Invoices => INVOICE_COLUMNS,
+            Self::Quotes => QUOTE_COLUMNS,
+            Self::Inventory => INVENTORY_COLUMNS,
+        }
+    }
+
+    // 复用列表查询的筛选条件，limit 为 0 表示不分页；结果逐行读取，不整体载入内存
+    async fn stream<'a>(self, db: &'a DbConn, args: ListArgs) -> Result<BoxStream<'a, Result<Value, DbErr>>, DbErr> {
+        let args = ListArgs { page: 1, limit: 0, ..args };
+        Ok(match self {
+            Self::Products => SelectProducts::find_by_statement(QueriesService::list_products_statement(&args))
+                .stream(db)
+                .await?
+                .map_ok(QueriesService::list_products_row)
+                .boxed(),
+            Self::Clients => SelectClients::find_by_statement(QueriesService::list_clients_statement(&args))
+                .stream(db)
+                .await?
+                .map_ok(QueriesService::list_clients_row)
+                .boxed(),
+            Self::Suppliers => SelectSuppliers::find_by_statement(QueriesService::list_suppliers_statement(&args))
+                .stream(db)
+                .await?
+                .map_ok(QueriesService::list_suppliers_row)
+                .boxed(),
+            Self::Orders => SelectOrders::find_by_statement(QueriesService::list_orders_statement(&args))
+                .stream(db)
+                .await?
+                .map_ok(QueriesService::list_orders_row)
+                .boxed(),
+            Self::Invoices => SelectInvoices::find_by_statement(QueriesService::list_invoices_statement(&args))
+                .stream(db)
+                .await?
+                .map_ok(QueriesService::list_invoices_row)
+                .boxed(),
+            Self::Quotes => SelectQuotes::find_by_statement(QueriesService::list_quotes_statement(&args))
+                .stream(db)
+                .await?
+                .map_ok(QueriesService::list_quotes_row)
+                .boxed(),
+            Self::Inventory => SelectInventory::find_by_statement(QueriesService::list_inventory_statement(&args))
+                .stream(db)
+                .await?
+                .map_ok(QueriesService::list_inventory_row)
+                .boxed(),
+        })
+    }
+}
+
+// 按前端语言（vue-i18n 的 locale，如 fr-FR）决定小数点、CSV 分隔符和日期格式
+struct ExportLocale {
+    decimal_comma: bool,
+    date_format: &'static str,
+    excel_date_format: &'static str,
+}
+
+impl ExportLocale {
+    fn new(locale: &str) -> Self {
+        let language = locale.split(['-', '_']).next().unwrap_or_default().to_ascii_lowercase();
+        match language.as_str() {
+            "fr" => Self {
+                decimal_comma: true,
+                date_format: "%d/%m/%Y %H:%M",
+                excel_date_format: "dd/mm/yyyy hh:mm",
+            },
+            "de" => Self {
+                decimal_comma: true,
+                date_format: "%d.%m.%Y %H:%M",
+                excel_date_format: "dd.mm.yyyy hh:mm",
+            },
+            "ar" => Self {
+                decimal_comma: false,
+                date_format: "%d/%m/%Y %H:%M",
+                excel_date_format: "dd/mm/yyyy hh:mm",
+            },
+            "en" => Self {
+                decimal_comma: false,
+                date_format: "%m/%d/%Y %H:%M",
+                excel_date_format: "mm/dd/yyyy hh:mm",
+            },
+            _ => Self {
+                decimal_comma: false,
+                date_format: "%Y-%m-%d %H:%M",
+                excel_date_format: "yyyy-mm-dd hh:mm",
+            },
+        }
+    }
+
+    // 小数点为逗号的语言里，Excel 打开 CSV 时以分号分列
+    fn csv_delimiter(&self) -> u8 {
+        if self.decimal_comma {
+            b';'
+        } else {
+            b','
+        }
+    }
+
+    fn format(&self, kind: Kind, value: &Value) -> String {
+        match (kind, value) {
+            (_, Value::Null) => String::new(),
+            (Kind::Integer, Value::Number(number)) => number.to_string(),
+            (Kind::Number, Value::Number(number)) => {
+                let text = format!("{:.2}", number.as_f64().unwrap_or_default());
+                if self.decimal_comma {
+                    text.replace('.', ",")
+                } else {
+                    text
+                }
+            }
+            (Kind::Date, Value::String(text)) => match NaiveDateTime::parse_from_str(text, DB_DATETIME) {
+                Ok(datetime) => datetime.format(self.date_format).to_string(),
+                Err(_) => text.clone(),
+            },
+            (_, Value::String(text)) => text.clone(),
+            (_, value) => value.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportReport {
+    pub path: String,
+    pub rows: usize,
+}
+
+fn io_error(path: &Path, err: impl std::fmt::Display) -> AppError {
+    AppError::Validation(format!("Could not write {}: {}", path.display(), err))
+}
+
+// 每读到一行就写入文件
+enum ExportWriter {
+    Csv(csv::Writer<BufWriter<File>>),
+    Xlsx {
+        workbook: Box<Workbook>,
+        row: u32,
+        number: Format,
+        date: Format,
+    },
+}
+
+impl ExportWriter {
+    fn create(path: &Path, format: ExportFormat, columns: &[Column], locale: &ExportLocale) -> Result<Self, AppError> {
+        match format {
+            ExportFormat::Csv => {
+                let mut file = BufWriter::new(File::create(path).map_err(|e| io_error(path, e))?);
+                // 写入 BOM，Excel 才能正确识别 UTF-8 中文
+                file.write_all("\u{feff}".as_bytes()).map_err(|e| io_error(path, e))?;
+                let mut writer = csv::WriterBuilder::new()
+                    .delimiter(locale.csv_delimiter())
+                    .from_writer(file);
+                writer
+                    .write_record(columns.iter().map(|column| column.header))
+                    .map_err(|e| io_error(path, e))?;
+                Ok(Self::Csv(writer))
+            }
+            ExportFormat::Xlsx => Self::create_xlsx(columns, locale).map_err(|e| io_error(path, e)),
+        }
+    }
+
+    // 工作表使用 constant memory 模式，写过的行会刷到临时文件；列宽需在写入数据前设置
+    fn create_xlsx(columns: &[Column], locale: &ExportLocale) -> Result<Self, XlsxError> {
+        let mut workbook = Workbook::new();
+        let worksheet = workbook.add_worksheet_with_constant_memory();
+        let header = Format::new().set_bold();
+        for (col, column) in (0u16..).zip(columns) {
+            let width = match column.kind {
+                Kind::Text => 24,
+                Kind::Date => 18,
+                Kind::Integer | Kind::Number => 12,
+            };
+            worksheet.set_column_width(col, width)?;
+            worksheet.write_string_with_format(0, col, column.header, &header)?;
+        }
+        worksheet.set_freeze_panes(1, 0)?;
+        Ok(Self::Xlsx {
+            workbook: Box::new(workbook),
+            row: 1,
+            number: Format::new().set_num_format("#,##0.00"),
+            date: Format::new().set_num_format(locale.excel_date_format),
+        })
+    }
+
+    fn write_row(&mut self, path: &Path, columns: &[Column], values: &Value, locale: &ExportLocale) -> Result<(), AppError> {
+        match self {
+            Self::Csv(writer) => writer
+                .write_record(columns.iter().map(|column| locale.format(column.kind, &values[column.key])))
+                .map_err(|e| io_error(path, e)),
+            Self::Xlsx {
+                workbook,
+                row,
+                number,
+                date,
+            } => {
+                write_xlsx_row(workbook, *row, columns, values, locale, number, date).map_err(|e| io_error(path, e))?;
+                *row += 1;
+                Ok(())
+            }
+        }
+    }
+
+    fn finish(self, path: &Path) -> Result<(), AppError> {
+        match self {
+            Self::Csv(mut writer) => writer.flush().map_err(|e| io_error(path, e)),
+            Self::Xlsx { mut workbook, .. } => workbook.save(path).map_err(|e| io_error(path, e)),
+        }
+    }
+}
+
+// XLSX 中数字和日期写为原生类型，千分位和小数点由 Excel 按系统区域显示
+fn write_xlsx_row(
+    workbook: &mut Workbook,
+    row: u32,
+    columns: &[Column],
+    values: &Value,
+    locale: &ExportLocale,
+    number: &Format,
+    date: &Format,
+) -> Result<(), XlsxError> {
+    let worksheet = workbook.worksheet_from_index(0)?;
+    for (col, column) in (0u16..).zip(columns) {
+        let value = &values[column.key];
+        match (column.kind, value) {
+            (_, Value::Null) => {}
+            (Kind::Integer, Value::Number(n)) => {
+                worksheet.write_number(row, col, n.as_f64().unwrap_or_default())?;
+            }
+            (Kind::Number, Value::Number(n)) => {
+                worksheet.write_number_with_format(row, col, n.as_f64().unwrap_or_default(), number)?;
+            }
+            (Kind::Date, Value::String(text)) => match NaiveDateTime::parse_from_str(text, DB_DATETIME) {
+                Ok(datetime) => {
+                    worksheet.write_datetime_with_format(row, col, datetime, date)?;
+                }
+                Err(_) => {
+                    worksheet.write_string(row, col, text)?;
+                }
+            },
+            (kind, value) => {
+                worksheet.write_string(row, col, locale.format(kind, value))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+pub async fn export_list(
+    db: &DbConn,
+    table: ExportTable,
+    format: ExportFormat,
+    args: ListArgs,
+    path: &Path,
+    locale: &str,
+) -> Result<ExportReport, AppError> {
+    let columns = table.columns();
+    let locale = ExportLocale::new(locale);
+    let mut rows = table.stream(db, args).await?;
+    let mut writer = ExportWriter::create(path, format, columns, &locale)?;
+    let mut count = 0;
+    while let Some(row) = rows.next().await {
+        writer.write_row(path, columns, &row?, &locale)?;
+        count += 1;
+    }
+    writer.finish(path)?;
+    Ok(ExportReport {
+        path: path.display().to_string(),
+        rows: count,
+    })
+}
+
+#[tauri::command]
+pub async fn export_list_to_file(
+    state: State<'_, AppState>,
+    table: ExportTable,
+    format: ExportFormat,
+    args: ListArgs,
+    path: String, // 前端通过 dialog.save 选择的路径
+    locale: Option<String>,
+) -> SResult<ExportReport> {
+    info!("Exporting {:?} as {:?} to {}", table, format, path);
+    let locale = locale.unwrap_or_default();
+    let res = export_list(state.db()?, table, format, args, Path::new(&path), &locale).await;
+    match res {
+        Ok(report) => Ok(Seccess {
+            error: None,
+            message: Some(format!("{} rows exported", report.rows)),
+            data: Some(report),
+        }),
+        Err(err) => {
+            error!("Export of {:?} failed: {}", table, err);
+            Err(err)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use migration::{Migrator, MigratorTrait};
+    use service::sea_orm::Database;
+    use service::{MutationsService, NewProduct};
+
+    fn args(search: &str) -> ListArgs {
+        ListArgs {
+            page: 1,
+            limit: 0,
+            search: search.to_string(),
+            status: None,
+            created_at: None,
+        }
+    }
+
+    #[test]
+    fn formats_numbers_and_dates_by_locale() {
+        let fr = ExportLocale::new("fr-FR");
+        assert_eq!(fr.format(Kind::Number, &Value::from(1234.5)), "1234,50");
+        assert_eq!(fr.format(Kind::Date, &Value::from("2024-03-10 09:30:00")), "10/03/2024 09:30");
+        let en = ExportLocale::new("en-US");
+        assert_eq!(en.format(Kind::Number, &Value::from(2)), "2.00");
+        assert_eq!(en.format(Kind::Date, &Value::from("2024-03-10 09:30:00")), "03/10/2024 09:30");
+        assert_eq!(en.format(Kind::Integer, &Value::from(3)), "3");
+        assert_eq!(en.format(Kind::Text, &Value::Null), "");
+    }
+
+    #[tokio::test]
+    async fn exports_every_filtered_row() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+        for i in 0..30 {
+            let product = NewProduct {
+                name: format!("{} {}", if i % 3 == 0 { "Book" } else { "Pen" }, i),
+                description: None,
+                price: 1.5,
+                min_quantity: 0.0,
+                image: None,
+            };
+            MutationsService::create_product(&db, product).await.unwrap();
+        }
+
+        let dir = std::env::temp_dir();
+        let csv_path = dir.join(format!("export-{}.csv", uuid::Uuid::now_v7()));
+        let report = export_list(&db, ExportTable::Products, ExportFormat::Csv, args("Pen"), &csv_path, "fr-FR")
+            .await
+            .unwrap();
+        assert_eq!(report.rows, 20);
+        let content = std::fs::read_to_string(&csv_path).unwrap();
+        std::fs::remove_file(&csv_path).unwrap();
+        let mut lines = content.trim_start_matches('\u{feff}').lines();
+        assert_eq!(lines.next(), Some("Name;Description;Price;Stock;Min quantity;Created at"));
+        assert!(lines.next().unwrap().contains(";1,50;0,00;0,00;"));
+        assert_eq!(lines.count(), 19);
+
+        let xlsx_path = dir.join(format!("export-{}.xlsx", uuid::Uuid::now_v7()));
+        let report = export_list(&db, ExportTable::Products, ExportFormat::Xlsx, args("Book"), &xlsx_path, "en-US")
+            .await
+            .unwrap();
+        assert_eq!(report.rows, 10);
+        assert!(std::fs::metadata(&xlsx_path).unwrap().len() > 0);
+        std::fs::remove_file(&xlsx_path).unwrap();
+    }
+}
@@ -0,0 +1,739 @@
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use service::sea_orm::DbConn;
+use service::{
+    ContactIdentity, ImportRecord, MutationsService, NewClient, NewInventory, NewProduct, NewSupplier, QueriesService,
+};
+use std::collections::HashMap;
+use std::path::Path;
+use tauri::{AppHandle, State};
+
+use crate::error::AppError;
+use crate::events::{self, Entity, Operation};
+use crate::AppState;
+
+use super::{SResult, Seccess};
+
+// CSV 可以导入的目标表，前端路由中的 table 参数
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportTable {
+    Clients,
+    Suppliers,
+    Products,
+    Inventory,
+}
+
+impl ImportTable {
+    pub fn parse(table: &str) -> Result<Self, AppError> {
+        match table.trim().to_ascii_lowercase().as_str() {
+            "clients" => Ok(Self::Clients),
+            "suppliers" => Ok(Self::Suppliers),
+            "products" => Ok(Self::Products),
+            "inventory" | "inventory_mouvements" => Ok(Self::Inventory),
+            other => Err(AppError::Validation(format!(
+                "CSV import is not supported for table '{}'; expected clients, suppliers, products or inventory.",
+                other
+            ))),
+        }
+    }
+
+    // 每张表可导入的列，第一项为必填列
+    fn columns(self) -> &'static [&'static str] {
+        match self {
+            Self::Clients | Self::Suppliers => &["full_name", "phone_number", "email", "address", "image"],
+            Self::Products => &["name", "price", "min_quantity", "description", "image"],
+            Self::Inventory => &["product_id", "mvm_type", "quantity"],
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RowStatus {
+    Inserted,
+    Updated,
+    Skipped,
+    Failed,
+}
+
+// 与已有记录重复的行如何处理，默认跳过
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum DuplicateAction {
+    #[default]
+    Skip,
+    // 更新匹配到的记录；id 为空时取第一条匹配的已有记录
+    Update { id: Option<String> },
+    Insert,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchField {
+    Email,
+    Phone,
+    FullName,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct DuplicateMatch {
+    // 已有记录的 id；与本文件中更早的行重复时为空，line 为该行行号
+    pub id: Option<String>,
+    pub line: Option<u64>,
+    pub full_name: String,
+    pub fields: Vec<MatchField>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RowReport {
+    // CSV 文件中的行号，表头为第 1 行
+    pub line: u64,
+    pub status: RowStatus,
+    pub id: Option<String>,
+    pub reason: Option<String>,
+    pub duplicates: Vec<DuplicateMatch>,
+    pub action: Option<DuplicateAction>,
+}
+
+impl RowReport {
+    fn new(line: u64, status: RowStatus, reason: Option<String>) -> Self {
+        Self {
+            line,
+            status,
+            id: None,
+            reason,
+            duplicates: Vec::new(),
+            action: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportReport {
+    pub table: ImportTable,
+    // 为 true 时没有写入任何数据，行状态表示正式导入时的结果
+    pub dry_run: bool,
+    pub inserted: usize,
+    pub updated: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    // 命中查重的行数
+    pub duplicates: usize,
+    pub rows: Vec<RowReport>,
+}
+
+#[derive(Debug, Default)]
+pub struct ImportOptions {
+    pub dry_run: bool,
+    // 按行号指定重复行的处理方式，未指定的行使用 on_duplicate
+    pub resolutions: HashMap<u64, DuplicateAction>,
+    pub on_duplicate: DuplicateAction,
+}
+
+impl ImportOptions {
+    fn action_for(&self, line: u64) -> DuplicateAction {
+        self.resolutions.get(&line).unwrap_or(&self.on_duplicate).clone()
+    }
+}
+
+// 列名比较时忽略大小写、首尾空格，空格和 - 视为 _
+fn normalize_header(header: &str) -> String {
+    header.trim().to_ascii_lowercase().replace([' ', '-'], "_")
+}
+
+// Excel 按区域设置导出的 CSV 常用 ; 分隔
+fn detect_delimiter(header_line: &str) -> u8 {
+    if header_line.matches(';').count() > header_line.matches(',').count() {
+        b';'
+    } else {
+        b','
+    }
+}
+
+// 将 CSV 表头映射到目标列：mapping 为 { CSV 表头: 列名 }，未映射的表头按同名列匹配
+fn resolve_columns(
+    table: ImportTable,
+    headers: &csv::StringRecord,
+    mapping: &HashMap<String, String>,
+) -> Result<HashMap<&'static str, usize>, AppError> {
+    let mapping: HashMap<String, String> = mapping
+        .iter()
+        .map(|(header, column)| (normalize_header(header), normalize_header(column)))
+        .collect();
+    let known = table.columns();
+
+    if let Some(column) = mapping.values().find(|column| !known.contains(&column.as_str())) {
+        return Err(AppError::Validation(format!(
+            "Unknown column '{}' in mapping; expected one of: {}.",
+            column,
+            known.join(", ")
+        )));
+    }
+
+    let mut columns = HashMap::new();
+    for (index, header) in headers.iter().enumerate() {
+        let header = normalize_header(header);
+        let column = mapping.get(&header).unwrap_or(&header);
+        if let Some(column) = known.iter().find(|known| *known == column) {
+            if columns.insert(*column, index).is_some() {
+                return Err(AppError::Validation(format!("Column '{}' is mapped more than once.", column)));
+            }
+        }
+    }
+
+    let required = known[0];
+    if !columns.contains_key(required) {
+        return Err(AppError::Validation(format!("Required column '{}' is missing from the CSV header.", required)));
+    }
+    Ok(columns)
+}
+
+struct Row<'a> {
+    record: &'a csv::StringRecord,
+    columns: &'a HashMap<&'static str, usize>,
+}
+
+impl Row<'_> {
+    fn text(&self, column: &str) -> Option<String> {
+        let index = *self.columns.get(column)?;
+        let value = self.record.get(index)?.trim();
+        (!value.is_empty()).then(|| value.to_string())
+    }
+
+    fn required(&self, column: &str) -> Result<String, String> {
+        self.text(column).ok_or_else(|| format!("'{}' is required", column))
+    }
+
+    fn number(&self, column: &str) -> Result<Option<f64>, String> {
+        match self.text(column) {
+            None => Ok(None),
+            Some(value) => match value.replace(',', ".").parse::<f64>() {
+                Ok(number) if number.is_finite() => Ok(Some(number)),
+                _ => Err(format!("'{}' must be a number, got '{}'", column, value)),
+            },
+        }
+    }
+
+    fn email(&self) -> Result<Option<String>, String> {
+        match self.text("email") {
+            Some(email) if !email.contains('@') => Err(format!("'email' is not a valid address: '{}'", email)),
+            email => Ok(email),
+        }
+    }
+}
+
+fn normalize_email(email: &str) -> Option<String> {
+    let email = email.trim().to_lowercase();
+    (!email.is_empty()).then_some(email)
+}
+
+// 只比较数字的最后 9 位，+33 6…、0033 6…、06… 视为同一号码；太短的号码不参与查重
+fn normalize_phone(phone: &str) -> Option<String> {
+    let digits: String = phone.chars().filter(char::is_ascii_digit).collect();
+    (digits.len() >= 6).then(|| digits[digits.len().saturating_sub(9)..].to_string())
+}
+
+// 忽略大小写、标点和多余空格
+fn normalize_name(name: &str) -> Option<String> {
+    let name = name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    (!name.is_empty()).then_some(name)
+}
+
+struct Contact<'a> {
+    full_name: &'a str,
+    email: Option<&'a str>,
+    phone_number: Option<&'a str>,
+}
+
+fn contact(record: &ImportRecord) -> Option<Contact<'_>> {
+    let (full_name, email, phone_number) = match record {
+        ImportRecord::Client(c) | ImportRecord::ClientUpdate { client: c, .. } => (&c.full_name, &c.email, &c.phone_number),
+        ImportRecord::Supplier(s) | ImportRecord::SupplierUpdate { supplier: s, .. } => {
+            (&s.full_name, &s.email, &s.phone_number)
+        }
+        _ => return None,
+    };
+    Some(Contact {
+        full_name,
+        email: email.as_deref(),
+        phone_number: phone_number.as_deref(),
+    })
+}
+
+fn into_update(record: ImportRecord, id: String) -> ImportRecord {
+    match record {
+        ImportRecord::Client(client) => ImportRecord::ClientUpdate { id, client },
+        ImportRecord::Supplier(supplier) => ImportRecord::SupplierUpdate { id, supplier },
+        record => record,
+    }
+}
+
+// 客户/供应商查重：已有记录加上本文件中已决定插入的行，按邮箱、电话、规范化姓名建立索引
+#[derive(Default)]
+struct DuplicateIndex {
+    known: Vec<DuplicateMatch>,
+    keys: HashMap<(MatchField, String), Vec<usize>>,
+}
+
+impl DuplicateIndex {
+    async fn load(db: &DbConn, table: ImportTable) -> Result<Option<Self>, AppError> {
+        let identities = match table {
+            ImportTable::Clients => QueriesService::list_client_identities(db).await?,
+            ImportTable::Suppliers => QueriesService::list_supplier_identities(db).await?,
+            _ => return Ok(None),
+        };
+        let mut index = Self::default();
+        for ContactIdentity {
+            id,
+            full_name,
+            phone_number,
+            email,
+        } in identities
+        {
+            let contact = Contact {
+                full_name: &full_name,
+                email: email.as_deref(),
+                phone_number: phone_number.as_deref(),
+            };
+            index.insert(Some(id), None, &contact);
+        }
+        Ok(Some(index))
+    }
+
+    fn keys(contact: &Contact) -> Vec<(MatchField, String)> {
+        [
+            (MatchField::Email, contact.email.and_then(normalize_email)),
+            (MatchField::Phone, contact.phone_number.and_then(normalize_phone)),
+            (MatchField::FullName, normalize_name(contact.full_name)),
+        ]
+        .into_iter()
+        .filter_map(|(field, key)| Some((field, key?)))
+        .collect()
+    }
+
+    fn insert(&mut self, id: Option<String>, line: Option<u64>, contact: &Contact) {
+        let position = self.known.len();
+        self.known.push(DuplicateMatch {
+            id,
+            line,
+            full_name: contact.full_name.to_string(),
+            fields: Vec::new(),
+        });
+        for key in Self::keys(contact) {
+            self.keys.entry(key).or_default().push(position);
+        }
+    }
+
+    // 匹配字段越多越靠前
+    fn find(&self, contact: &Contact) -> Vec<DuplicateMatch> {
+        let mut fields: HashMap<usize, Vec<MatchField>> = HashMap::new();
+        for key in Self::keys(contact) {
+            for position in self.keys.get(&key).into_iter().flatten() {
+                fields.entry(*position).or_default().push(key.0);
+            }
+        }
+        let mut matches: Vec<(usize, Vec<MatchField>)> = fields.into_iter().collect();
+        matches.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then(a.0.cmp(&b.0)));
+        matches
+            .into_iter()
+            .map(|(position, fields)| DuplicateMatch {
+                fields,
+                ..self.known[position].clone()
+            })
+            .collect()
+    }
+}
+
+fn describe(duplicate: &DuplicateMatch) -> String {
+    match duplicate.line {
+        Some(line) => format!("duplicate of line {} ({})", line, duplicate.full_name),
+        None => format!("duplicate of existing record {}", duplicate.full_name),
+    }
+}
+
+// 校验一行并转换为待插入记录；空行返回 None
+fn parse_row(table: ImportTable, row: &Row) -> Result<Option<ImportRecord>, String> {
+    if row.record.iter().all(|value| value.trim().is_empty()) {
+        return Ok(None);
+    }
+
+    let record = match table {
+        ImportTable::Clients => ImportRecord::Client(NewClient {
+            full_name: row.required("full_name")?,
+            email: row.email()?,
+            phone_number: row.text("phone_number"),
+            address: row.text("address"),
+            image: row.text("image"),
+        }),
+        ImportTable::Suppliers => ImportRecord::Supplier(NewSupplier {
+            full_name: row.required("full_name")?,
+            email: row.email()?,
+            phone_number: row.text("phone_number"),
+            address: row.text("address"),
+            image: row.text("image"),
+        }),
+        ImportTable::Products => {
+            let price = row.number("price")?.unwrap_or(0.0);
+            let min_quantity = row.number("min_quantity")?.unwrap_or(0.0);
+            if price < 0.0 || min_quantity < 0.0 {
+                return Err("'price' and 'min_quantity' cannot be negative".to_string());
+            }
+            ImportRecord::Product(NewProduct {
+                name: row.required("name")?,
+                price,
+                min_quantity,
+                description: row.text("description"),
+                image: row.text("image"),
+            })
+        }
+        ImportTable::Inventory => {
+            let mvm_type = row.required("mvm_type")?.to_ascii_uppercase();
+            if mvm_type != "IN" && mvm_type != "OUT" {
+                return Err(format!("'mvm_type' must be IN or OUT, got '{}'", mvm_type));
+            }
+            let quantity = row.number("quantity")?.ok_or_else(|| "'quantity' is required".to_string())?;
+            if quantity <= 0.0 {
+                return Err("'quantity' must be greater than 0".to_string());
+            }
+            ImportRecord::Inventory(NewInventory {
+                product_id: row.required("product_id")?,
+                mvm_type,
+                quantity,
+            })
+        }
+    };
+    Ok(Some(record))
+}
+
+pub async fn import_csv(
+    db: &DbConn,
+    path: &Path,
+    table: ImportTable,
+    mapping: &HashMap<String, String>,
+    options: &ImportOptions,
+) -> Result<ImportReport, AppError> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| AppError::Validation(format!("Could not read {}: {}", path.display(), e)))?;
+    let delimiter = detect_delimiter(content.lines().next().unwrap_or_default());
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_reader(content.trim_start_matches('\u{feff}').as_bytes());
+
+    let headers = reader
+        .headers()
+        .map_err(|e| AppError::Validation(format!("Invalid CSV header: {}", e)))?
+        .clone();
+    let columns = resolve_columns(table, &headers, mapping)?;
+    let mut duplicates = DuplicateIndex::load(db, table).await?;
+
+    // 先校验全部行，只有通过校验的行进入事务
+    let mut rows = Vec::new();
+    let mut records = Vec::new();
+    let mut pending = Vec::new();
+    for (index, result) in reader.records().enumerate() {
+        let record = match result {
+            Ok(record) => record,
+            Err(e) => {
+                let line = e.position().map_or(index as u64 + 2, |p| p.line());
+                rows.push(RowReport::new(line, RowStatus::Failed, Some(e.to_string())));
+                continue;
+            }
+        };
+        let line = record.position().map_or(index as u64 + 2, |p| p.line());
+        match parse_row(table, &Row { record: &record, columns: &columns }) {
+            Ok(Some(parsed)) => {
+                let mut report = RowReport::new(line, RowStatus::Inserted, None);
+                let matches = match (&duplicates, contact(&parsed)) {
+                    (Some(index), Some(contact)) => index.find(&contact),
+                    _ => Vec::new(),
+                };
+                let record = if matches.is_empty() {
+                    Some(parsed)
+                } else {
+                    let action = options.action_for(line);
+                    let record = match &action {
+                        DuplicateAction::Skip => {
+                            report.status = RowStatus::Skipped;
+                            report.reason = Some(describe(&matches[0]));
+                            None
+                        }
+                        DuplicateAction::Insert => Some(parsed),
+                        DuplicateAction::Update { id } => {
+                            match id.clone().or_else(|| matches.iter().find_map(|m| m.id.clone())) {
+                                Some(id) => {
+                                    report.status = RowStatus::Updated;
+                                    Some(into_update(parsed, id))
+                                }
+                                None => {
+                                    report.status = RowStatus::Skipped;
+                                    report.reason = Some(format!("{}; no existing record to update", describe(&matches[0])));
+                                    None
+                                }
+                            }
+                        }
+                    };
+                    report.duplicates = matches;
+                    report.action = Some(action);
+                    record
+                };
+                if let Some(record) = record {
+                    // 本文件中后面的行也要与将要插入的行查重
+                    if let (Some(index), RowStatus::Inserted, Some(contact)) =
+                        (duplicates.as_mut(), report.status, contact(&record))
+                    {
+                        index.insert(None, Some(line), &contact);
+                    }
+                    pending.push(rows.len());
+                    records.push(record);
+                }
+                rows.push(report);
+            }
+            Ok(None) => rows.push(RowReport::new(line, RowStatus::Skipped, Some("empty row".to_string()))),
+            Err(reason) => rows.push(RowReport::new(line, RowStatus::Failed, Some(reason))),
+        }
+    }
+
+    let results = MutationsService::import_records(db, records, options.dry_run).await?;
+    for (index, result) in pending.into_iter().zip(results) {
+        match result {
+            // 预览时事务已回滚，生成的 id 没有意义
+            Ok(id) => rows[index].id = (!options.dry_run).then_some(id),
+            Err(err) => {
+                rows[index].status = RowStatus::Failed;
+                rows[index].reason = Some(AppError::from(err).message().to_string());
+            }
+        }
+    }
+
+    let count = |status| rows.iter().filter(|row| row.status == status).count();
+    Ok(ImportReport {
+        table,
+        dry_run: options.dry_run,
+        inserted: count(RowStatus::Inserted),
+        updated: count(RowStatus::Updated),
+        skipped: count(RowStatus::Skipped),
+        failed: count(RowStatus::Failed),
+        duplicates: rows.iter().filter(|row| !row.duplicates.is_empty()).count(),
+        rows,
+    })
+}
+
+impl ImportTable {
+    fn entity(self) -> Entity {
+        match self {
+            Self::Clients => Entity::Clients,
+            Self::Suppliers => Entity::Suppliers,
+            Self::Products => Entity::Products,
+            Self::Inventory => Entity::Inventory,
+        }
+    }
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn upload_csv_to_db(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    csv_path: String,
+    table: String,
+    mapping: Option<HashMap<String, String>>, // { CSV 表头: 列名 }
+    dry_run: Option<bool>,                    // 只返回预览报告，不写入
+    resolutions: Option<HashMap<u64, DuplicateAction>>, // { 行号: 处理方式 }
+    on_duplicate: Option<DuplicateAction>,
+) -> SResult<ImportReport> {
+    info!("Importing {} into {}", csv_path, table);
+    let options = ImportOptions {
+        dry_run: dry_run.unwrap_or(false),
+        resolutions: resolutions.unwrap_or_default(),
+        on_duplicate: on_duplicate.unwrap_or_default(),
+    };
+    let res = match ImportTable::parse(&table).and_then(|table| Ok((table, state.db()?))) {
+        Ok((table, db)) => {
+            let res = import_csv(db, Path::new(&csv_path), table, &mapping.unwrap_or_default(), &options).await;
+            if matches!(&res, Ok(report) if !report.dry_run && report.inserted + report.updated > 0) {
+                events::db_change(&app, table.entity(), None, Operation::Imported);
+            }
+            res
+        }
+        Err(err) => Err(err),
+    };
+    match res {
+        Ok(report) => {
+            info!(
+                "CSV import into {}{}: {} inserted, {} updated, {} skipped, {} failed, {} duplicates",
+                table,
+                if report.dry_run { " (dry run)" } else { "" },
+                report.inserted,
+                report.updated,
+                report.skipped,
+                report.failed,
+                report.duplicates
+            );
+            let message = if report.dry_run {
+                format!("{} rows ready to import", report.inserted + report.updated)
+            } else {
+                format!("{} rows imported", report.inserted + report.updated)
+            };
+            Ok(Seccess {
+                error: None,
+                message: Some(message),
+                data: Some(report),
+            })
+        }
+        Err(err) => {
+            error!("CSV import into {} failed: {}", table, err);
+            Err(err)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use migration::{Migrator, MigratorTrait};
+    use service::sea_orm::Database;
+
+    async fn database() -> DbConn {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+        db
+    }
+
+    fn write_csv(name: &str, content: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("{}-{}.csv", name, uuid::Uuid::now_v7()));
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn imports_valid_rows_and_reports_the_rest() {
+        let db = database().await;
+        let path = write_csv(
+            "products",
+            "Product Name;Price;min_quantity\nPen;1,5;10\n;2;1\n;;\nBook;abc;1\nBag;20;\n",
+        );
+        let mapping = HashMap::from([("Product Name".to_string(), "name".to_string())]);
+
+        let report = import_csv(&db, &path, ImportTable::Products, &mapping, &ImportOptions::default()).await.unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!((report.inserted, report.skipped, report.failed), (2, 1, 2));
+        let statuses: Vec<(u64, RowStatus)> = report.rows.iter().map(|row| (row.line, row.status)).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                (2, RowStatus::Inserted),
+                (3, RowStatus::Failed),
+                (4, RowStatus::Skipped),
+                (5, RowStatus::Failed),
+                (6, RowStatus::Inserted),
+            ]
+        );
+        assert_eq!(report.rows[1].reason.as_deref(), Some("'name' is required"));
+        assert!(report.rows[0].id.is_some());
+    }
+
+    #[tokio::test]
+    async fn database_errors_fail_only_their_row() {
+        let db = database().await;
+        let products = write_csv("products", "name,price\nPen,2\n");
+        let product_id = import_csv(&db, &products, ImportTable::Products, &HashMap::new(), &ImportOptions::default())
+            .await
+            .unwrap()
+            .rows[0]
+            .id
+            .clone()
+            .unwrap();
+        std::fs::remove_file(products).unwrap();
+
+        let path = write_csv(
+            "inventory",
+            &format!("product_id,mvm_type,quantity\n{0},in,5\nmissing,IN,1\n{0},OUT,2\n", product_id),
+        );
+        let report = import_csv(&db, &path, ImportTable::Inventory, &HashMap::new(), &ImportOptions::default()).await.unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!((report.inserted, report.skipped, report.failed), (2, 0, 1));
+        assert_eq!(report.rows[1].status, RowStatus::Failed);
+        assert!(report.rows[2].id.is_some());
+    }
+
+    #[tokio::test]
+    async fn previews_and_resolves_duplicates() {
+        let db = database().await;
+        let existing = NewClient {
+            full_name: "Jane Doe".to_string(),
+            email: Some("jane@example.com".to_string()),
+            phone_number: Some("+33 6 12 34 56 78".to_string()),
+            address: None,
+            image: None,
+        };
+        let jane = MutationsService::create_client(&db, existing).await.unwrap();
+        let before = QueriesService::list_client_identities(&db).await.unwrap().len();
+        let path = write_csv(
+            "clients",
+            "full_name,email,phone_number,address\n\
+             J. Doe,JANE@example.com,,Paris\n\
+             Other Jane,,0033612345678,\n\
+             john  SMITH,john@example.com,,\n\
+             John Smith,,,\n",
+        );
+
+        let preview = ImportOptions {
+            dry_run: true,
+            ..Default::default()
+        };
+        let report = import_csv(&db, &path, ImportTable::Clients, &HashMap::new(), &preview).await.unwrap();
+        assert_eq!(QueriesService::list_client_identities(&db).await.unwrap().len(), before);
+        assert_eq!((report.inserted, report.skipped, report.duplicates), (1, 3, 3));
+        assert_eq!(report.rows[0].duplicates[0].id.as_deref(), Some(jane.as_str()));
+        assert_eq!(report.rows[0].duplicates[0].fields, vec![MatchField::Email]);
+        assert_eq!(report.rows[1].duplicates[0].fields, vec![MatchField::Phone]);
+        assert_eq!(report.rows[3].duplicates[0].line, Some(4));
+        assert!(report.rows.iter().all(|row| row.id.is_none()));
+
+        let options = ImportOptions {
+            resolutions: HashMap::from([
+                (2, DuplicateAction::Update { id: None }),
+                (3, DuplicateAction::Insert),
+            ]),
+            ..Default::default()
+        };
+        let report = import_csv(&db, &path, ImportTable::Clients, &HashMap::new(), &options).await.unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        let statuses: Vec<RowStatus> = report.rows.iter().map(|row| row.status).collect();
+        assert_eq!(
+            statuses,
+            vec![RowStatus::Updated, RowStatus::Inserted, RowStatus::Inserted, RowStatus::Skipped]
+        );
+        assert_eq!(report.rows[0].id.as_deref(), Some(jane.as_str()));
+        let identities = QueriesService::list_client_identities(&db).await.unwrap();
+        assert_eq!(identities.len(), before + 2);
+        let jane = identities.iter().find(|c| c.id == jane).unwrap();
+        assert_eq!(jane.full_name, "J. Doe");
+        assert_eq!(jane.phone_number.as_deref(), Some("+33 6 12 34 56 78"));
+    }
+
+    #[test]
+    fn rejects_unknown_tables_and_columns() {
+        assert_eq!(ImportTable::parse("inventory_mouvements").unwrap(), ImportTable::Inventory);
+        assert_eq!(ImportTable::parse("orders").unwrap_err().code(), "VALIDATION");
+
+        let headers = csv::StringRecord::from(vec!["Nom", "Tel"]);
+        let mapping = HashMap::from([("Nom".to_string(), "nom".to_string())]);
+        assert!(resolve_columns(ImportTable::Clients, &headers, &mapping).is_err());
+        let mapping = HashMap::from([("Nom".to_string(), "full_name".to_string())]);
+        let columns = resolve_columns(ImportTable::Clients, &headers, &mapping).unwrap();
+        assert_eq!(columns["full_name"], 0);
+    }
+}
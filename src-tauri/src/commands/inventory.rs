@@ -1,15 +1,16 @@
 use serde_json::Value;
 use service::{Inventory, ListArgs, MutationsService, NewInventory, QueriesService};
-use tauri::State;
+use tauri::{AppHandle, State};
 
-use crate::{commands::Fail, AppState};
+use crate::events::{self, Entity, Operation};
+use crate::AppState;
 
 use super::{SResult, Seccess};
 
 #[tauri::command]
 pub async fn list_inventory(state: State<'_, AppState>, args: ListArgs) -> SResult<Value> {
-    let _ = state.db_conn;
-    let res = QueriesService::list_inventory(&state.db_conn, args).await;
+    let db = state.db()?;
+    let res = QueriesService::list_inventory(db, args).await;
     match res {
         Ok(res) => Ok(Seccess {
             error: None,
@@ -18,18 +19,18 @@ pub async fn list_inventory(state: State<'_, AppState>, args: ListArgs) -> SResu
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
 
 #[tauri::command]
-pub async fn create_inventory(state: State<'_, AppState>, mvm: NewInventory) -> SResult<String> {
-    let _ = state.db_conn;
-    let res = MutationsService::create_inv_mvm(&state.db_conn, mvm).await;
+pub async fn create_inventory(app: AppHandle, state: State<'_, AppState>, mvm: NewInventory) -> SResult<String> {
+    let db = state.db()?;
+    let res = MutationsService::create_inv_mvm(db, mvm).await;
+    if let Ok(id) = &res {
+        events::db_change(&app, Entity::Inventory, Some(id.clone()), Operation::Created);
+    }
     match res {
         Ok(id) => Ok(Seccess::<String> {
             error: None,
@@ -38,18 +39,19 @@ pub async fn create_inventory(state: State<'_, AppState>, mvm: NewInventory) ->
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
 
 #[tauri::command]
-pub async fn delete_inventory(state: State<'_, AppState>, id: String) -> SResult<String> {
-    let _ = state.db_conn;
-    let res = MutationsService::delete_inv_mvm(&state.db_conn, id).await;
+pub async fn delete_inventory(app: AppHandle, state: State<'_, AppState>, id: String) -> SResult<String> {
+    let db = state.db()?;
+    let deleted = id.clone();
+    let res = MutationsService::delete_inv_mvm(db, id).await;
+    if matches!(res, Ok(rows) if rows > 0) {
+        events::db_change(&app, Entity::Inventory, Some(deleted), Operation::Deleted);
+    }
     match res {
         Ok(_) => Ok(Seccess::<String> {
             error: None,
@@ -58,18 +60,19 @@ pub async fn delete_inventory(state: State<'_, AppState>, id: String) -> SResult
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
 
 #[tauri::command]
-pub async fn update_inventory(state: State<'_, AppState>, mvm: Inventory) -> SResult<String> {
-    let _ = state.db_conn;
-    let res = MutationsService::update_inv_mvm(&state.db_conn, mvm).await;
+pub async fn update_inventory(app: AppHandle, state: State<'_, AppState>, mvm: Inventory) -> SResult<String> {
+    let db = state.db()?;
+    let updated = mvm.id.clone();
+    let res = MutationsService::update_inv_mvm(db, mvm).await;
+    if res.is_ok() {
+        events::db_change(&app, Entity::Inventory, Some(updated), Operation::Updated);
+    }
     match res {
         Ok(_) => Ok(Seccess::<String> {
             error: None,
@@ -78,10 +81,7 @@ pub async fn update_inventory(state: State<'_, AppState>, mvm: Inventory) -> SRe
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
@@ -1,17 +1,18 @@
 use service::{InvoiceItem, MutationsService, NewInvoiceItem};
-use tauri::State;
+use tauri::{AppHandle, State};
 
+use crate::events::{self, Entity, Operation};
 use crate::AppState;
 
-use super::{Fail, SResult, Seccess};
+use super::{SResult, Seccess};
 
 #[tauri::command]
-pub async fn create_invoice_item(
-    state: State<'_, AppState>,
-    item: NewInvoiceItem,
-) -> SResult<String> {
-    let _ = state.db_conn;
-    let res = MutationsService::create_invoice_item(&state.db_conn, item).await;
+pub async fn create_invoice_item(app: AppHandle, state: State<'_, AppState>, item: NewInvoiceItem) -> SResult<String> {
+    let db = state.db()?;
+    let res = MutationsService::create_invoice_item(db, item).await;
+    if let Ok(id) = &res {
+        events::db_change(&app, Entity::InvoiceItems, Some(id.clone()), Operation::Created);
+    }
     match res {
         Ok(res) => Ok(Seccess {
             error: None,
@@ -20,18 +21,19 @@ pub async fn create_invoice_item(
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
 
 #[tauri::command]
-pub async fn update_invoice_item(state: State<'_, AppState>, item: InvoiceItem) -> SResult<()> {
-    let _ = state.db_conn;
-    let res = MutationsService::update_invoice_item(&state.db_conn, item).await;
+pub async fn update_invoice_item(app: AppHandle, state: State<'_, AppState>, item: InvoiceItem) -> SResult<()> {
+    let db = state.db()?;
+    let updated = item.id.clone();
+    let res = MutationsService::update_invoice_item(db, item).await;
+    if res.is_ok() {
+        events::db_change(&app, Entity::InvoiceItems, Some(updated), Operation::Updated);
+    }
     match res {
         Ok(res) => Ok(Seccess {
             error: None,
@@ -40,18 +42,19 @@ pub async fn update_invoice_item(state: State<'_, AppState>, item: InvoiceItem)
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
 
 #[tauri::command]
-pub async fn delete_invoice_item(state: State<'_, AppState>, id: String) -> SResult<u64> {
-    let _ = state.db_conn;
-    let res = MutationsService::delete_invoice_item(&state.db_conn, id).await;
+pub async fn delete_invoice_item(app: AppHandle, state: State<'_, AppState>, id: String) -> SResult<u64> {
+    let db = state.db()?;
+    let deleted = id.clone();
+    let res = MutationsService::delete_invoice_item(db, id).await;
+    if matches!(res, Ok(rows) if rows > 0) {
+        events::db_change(&app, Entity::InvoiceItems, Some(deleted), Operation::Deleted);
+    }
     match res {
         Ok(res) => Ok(Seccess {
             error: None,
@@ -60,10 +63,7 @@ pub async fn delete_invoice_item(state: State<'_, AppState>, id: String) -> SRes
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
@@ -1,15 +1,16 @@
 use serde_json::Value;
-use service::{Invoice, ListArgs, MutationsService, NewInvoice, QueriesService};
-use tauri::State;
+use service::{Invoice, ListArgs, MutationsService, NewInvoice, NewInvoiceWithItems, QueriesService};
+use tauri::{AppHandle, State};
 
+use crate::events::{self, Entity, Operation};
 use crate::AppState;
 
-use super::{Fail, SResult, Seccess};
+use super::{SResult, Seccess};
 
 #[tauri::command]
 pub async fn list_invoices(state: State<'_, AppState>, args: ListArgs) -> SResult<Value> {
-    let _ = state.db_conn;
-    let res = QueriesService::list_invoices(&state.db_conn, args).await;
+    let db = state.db()?;
+    let res = QueriesService::list_invoices(db, args).await;
     match res {
         Ok(res) => Ok(Seccess {
             error: None,
@@ -18,18 +19,15 @@ pub async fn list_invoices(state: State<'_, AppState>, args: ListArgs) -> SResul
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
 
 #[tauri::command]
 pub async fn list_invoice_products(state: State<'_, AppState>, id: String) -> SResult<Vec<Value>> {
-    let _ = state.db_conn;
-    let res = QueriesService::list_invoice_products(&state.db_conn, id).await;
+    let db = state.db()?;
+    let res = QueriesService::list_invoice_products(db, id).await;
     match res {
         Ok(res) => Ok(Seccess {
             error: None,
@@ -38,18 +36,18 @@ pub async fn list_invoice_products(state: State<'_, AppState>, id: String) -> SR
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
 
 #[tauri::command]
-pub async fn create_invoice(state: State<'_, AppState>, invoice: NewInvoice) -> SResult<String> {
-    let _ = state.db_conn;
-    let res = MutationsService::create_invoice(&state.db_conn, invoice).await;
+pub async fn create_invoice(app: AppHandle, state: State<'_, AppState>, invoice: NewInvoice) -> SResult<String> {
+    let db = state.db()?;
+    let res = MutationsService::create_invoice(db, invoice).await;
+    if let Ok(id) = &res {
+        events::db_change(&app, Entity::Invoices, Some(id.clone()), Operation::Created);
+    }
     match res {
         Ok(id) => Ok(Seccess {
             error: None,
@@ -58,18 +56,39 @@ pub async fn create_invoice(state: State<'_, AppState>, invoice: NewInvoice) ->
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
 
 #[tauri::command]
-pub async fn update_invoice(state: State<'_, AppState>, invoice: Invoice) -> SResult<()> {
-    let _ = state.db_conn;
-    let res = MutationsService::update_invoice(&state.db_conn, invoice).await;
+pub async fn create_invoice_with_items(app: AppHandle, state: State<'_, AppState>, invoice: NewInvoiceWithItems) -> SResult<Value> {
+    let db = state.db()?;
+    let res = MutationsService::create_invoice_with_items(db, invoice).await;
+    if let Ok(created) = &res {
+        events::document_created(&app, Entity::Invoices, Entity::InvoiceItems, created);
+    }
+    match res {
+        Ok(res) => Ok(Seccess {
+            error: None,
+            message: None,
+            data: Some(res),
+        }),
+        Err(err) => {
+            println!("Error: {}", err);
+            Err(err.into())
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn update_invoice(app: AppHandle, state: State<'_, AppState>, invoice: Invoice) -> SResult<()> {
+    let db = state.db()?;
+    let updated = invoice.id.clone();
+    let res = MutationsService::update_invoice(db, invoice).await;
+    if res.is_ok() {
+        events::db_change(&app, Entity::Invoices, Some(updated), Operation::Updated);
+    }
     match res {
         Ok(_) => Ok(Seccess {
             error: None,
@@ -78,18 +97,19 @@ pub async fn update_invoice(state: State<'_, AppState>, invoice: Invoice) -> SRe
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
 
 #[tauri::command]
-pub async fn delete_invoice(state: State<'_, AppState>, id: String) -> SResult<u64> {
-    let _ = state.db_conn;
-    let res = MutationsService::delete_invoice(&state.db_conn, id).await;
+pub async fn delete_invoice(app: AppHandle, state: State<'_, AppState>, id: String) -> SResult<u64> {
+    let db = state.db()?;
+    let deleted = id.clone();
+    let res = MutationsService::delete_invoice(db, id).await;
+    if matches!(res, Ok(rows) if rows > 0) {
+        events::db_change(&app, Entity::Invoices, Some(deleted), Operation::Deleted);
+    }
     match res {
         Ok(res) => Ok(Seccess {
             error: None,
@@ -98,18 +118,15 @@ pub async fn delete_invoice(state: State<'_, AppState>, id: String) -> SResult<u
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
 
 #[tauri::command]
 pub async fn get_invoice(state: State<'_, AppState>, id: String) -> SResult<Value> {
-    let _ = state.db_conn;
-    let res = QueriesService::get_invoice(&state.db_conn, id).await;
+    let db = state.db()?;
+    let res = QueriesService::get_invoice(db, id).await;
     match res {
         Ok(res) => Ok(Seccess {
             error: None,
@@ -118,18 +135,15 @@ pub async fn get_invoice(state: State<'_, AppState>, id: String) -> SResult<Valu
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
 
 #[tauri::command]
 pub async fn get_invoice_details(state: State<'_, AppState>, id: String) -> SResult<Value> {
-    let _ = state.db_conn;
-    let res = QueriesService::get_invoice_details(&state.db_conn, id).await;
+    let db = state.db()?;
+    let res = QueriesService::get_invoice_details(db, id).await;
     match res {
         Ok(res) => Ok(Seccess {
             error: None,
@@ -138,10 +152,7 @@ pub async fn get_invoice_details(state: State<'_, AppState>, id: String) -> SRes
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
@@ -1,7 +1,12 @@
+use log::error;
 use serde::{Deserialize, Serialize};
 
+use crate::error::AppError;
+
 pub mod clients;
 pub mod dashboard;
+pub mod export;
+pub mod import;
 pub mod inventory;
 pub mod invoice_items;
 pub mod invoices;
@@ -11,6 +16,7 @@ pub mod products;
 pub mod quote_items;
 pub mod quotes;
 pub mod suppliers;
+pub mod user;
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct Seccess<T> {
@@ -19,10 +25,19 @@ pub struct Seccess<T> {
     pub data: Option<T>,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
-pub struct Fail {
-    pub error: Option<String>,
-    pub message: Option<String>,
-}
+pub type SResult<T> = Result<Seccess<T>, AppError>;
 
-pub type SResult<T> = Result<Seccess<T>, Fail>;
+// 把结果包装成 Seccess，错误写入日志（经过脱敏）后原样返回
+pub fn respond<T>(res: Result<T, AppError>, message: Option<&str>) -> SResult<T> {
+    match res {
+        Ok(data) => Ok(Seccess {
+            error: None,
+            message: message.map(String::from),
+            data: Some(data),
+        }),
+        Err(err) => {
+            error!("Error: {}", err);
+            Err(err)
+        }
+    }
+}
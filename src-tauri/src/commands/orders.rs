@@ -1,15 +1,16 @@
 use serde_json::Value;
-use service::{ListArgs, MutationsService, NewOrder, Order, QueriesService};
-use tauri::State;
+use service::{ListArgs, MutationsService, NewOrder, NewOrderWithItems, Order, QueriesService};
+use tauri::{AppHandle, State};
 
+use crate::events::{self, Entity, Operation};
 use crate::AppState;
 
-use super::{Fail, SResult, Seccess};
+use super::{SResult, Seccess};
 
 #[tauri::command]
 pub async fn list_orders(state: State<'_, AppState>, args: ListArgs) -> SResult<Value> {
-    let _ = state.db_conn;
-    let res = QueriesService::list_orders(&state.db_conn, args).await;
+    let db = state.db()?;
+    let res = QueriesService::list_orders(db, args).await;
     match res {
         Ok(res) => Ok(Seccess {
             error: None,
@@ -18,18 +19,15 @@ pub async fn list_orders(state: State<'_, AppState>, args: ListArgs) -> SResult<
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
 
 #[tauri::command]
 pub async fn list_order_products(state: State<'_, AppState>, id: String) -> SResult<Vec<Value>> {
-    let _ = state.db_conn;
-    let res = QueriesService::list_order_products(&state.db_conn, id).await;
+    let db = state.db()?;
+    let res = QueriesService::list_order_products(db, id).await;
     match res {
         Ok(res) => Ok(Seccess {
             error: None,
@@ -38,18 +36,18 @@ pub async fn list_order_products(state: State<'_, AppState>, id: String) -> SRes
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
 
 #[tauri::command]
-pub async fn create_order(state: State<'_, AppState>, order: NewOrder) -> SResult<String> {
-    let _ = state.db_conn;
-    let res = MutationsService::create_order(&state.db_conn, order).await;
+pub async fn create_order(app: AppHandle, state: State<'_, AppState>, order: NewOrder) -> SResult<String> {
+    let db = state.db()?;
+    let res = MutationsService::create_order(db, order).await;
+    if let Ok(id) = &res {
+        events::db_change(&app, Entity::Orders, Some(id.clone()), Operation::Created);
+    }
     match res {
         Ok(id) => Ok(Seccess {
             error: None,
@@ -58,18 +56,39 @@ pub async fn create_order(state: State<'_, AppState>, order: NewOrder) -> SResul
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
 
 #[tauri::command]
-pub async fn update_order(state: State<'_, AppState>, order: Order) -> SResult<()> {
-    let _ = state.db_conn;
-    let res = MutationsService::update_order(&state.db_conn, order).await;
+pub async fn create_order_with_items(app: AppHandle, state: State<'_, AppState>, order: NewOrderWithItems) -> SResult<Value> {
+    let db = state.db()?;
+    let res = MutationsService::create_order_with_items(db, order).await;
+    if let Ok(created) = &res {
+        events::document_created(&app, Entity::Orders, Entity::OrderItems, created);
+    }
+    match res {
+        Ok(res) => Ok(Seccess {
+            error: None,
+            message: None,
+            data: Some(res),
+        }),
+        Err(err) => {
+            println!("Error: {}", err);
+            Err(err.into())
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn update_order(app: AppHandle, state: State<'_, AppState>, order: Order) -> SResult<()> {
+    let db = state.db()?;
+    let updated = order.id.clone();
+    let res = MutationsService::update_order(db, order).await;
+    if res.is_ok() {
+        events::db_change(&app, Entity::Orders, Some(updated), Operation::Updated);
+    }
     match res {
         Ok(_) => Ok(Seccess {
             error: None,
@@ -78,18 +97,19 @@ pub async fn update_order(state: State<'_, AppState>, order: Order) -> SResult<(
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
 
 #[tauri::command]
-pub async fn delete_order(state: State<'_, AppState>, id: String) -> SResult<u64> {
-    let _ = state.db_conn;
-    let res = MutationsService::delete_order(&state.db_conn, id).await;
+pub async fn delete_order(app: AppHandle, state: State<'_, AppState>, id: String) -> SResult<u64> {
+    let db = state.db()?;
+    let deleted = id.clone();
+    let res = MutationsService::delete_order(db, id).await;
+    if matches!(res, Ok(rows) if rows > 0) {
+        events::db_change(&app, Entity::Orders, Some(deleted), Operation::Deleted);
+    }
     match res {
         Ok(res) => Ok(Seccess {
             error: None,
@@ -98,18 +118,15 @@ pub async fn delete_order(state: State<'_, AppState>, id: String) -> SResult<u64
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
 
 #[tauri::command]
 pub async fn get_order(state: State<'_, AppState>, id: String) -> SResult<Value> {
-    let _ = state.db_conn;
-    let res = QueriesService::get_order(&state.db_conn, id).await;
+    let db = state.db()?;
+    let res = QueriesService::get_order(db, id).await;
     match res {
         Ok(res) => Ok(Seccess {
             error: None,
@@ -118,18 +135,15 @@ pub async fn get_order(state: State<'_, AppState>, id: String) -> SResult<Value>
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
 
 #[tauri::command]
 pub async fn get_order_details(state: State<'_, AppState>, id: String) -> SResult<Value> {
-    let _ = state.db_conn;
-    let res = QueriesService::get_order_details(&state.db_conn, id).await;
+    let db = state.db()?;
+    let res = QueriesService::get_order_details(db, id).await;
     match res {
         Ok(res) => Ok(Seccess {
             error: None,
@@ -138,10 +152,7 @@ pub async fn get_order_details(state: State<'_, AppState>, id: String) -> SResul
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
@@ -1,15 +1,16 @@
 use serde_json::Value;
 use service::{ListArgs, MutationsService, NewProduct, Product, QueriesService};
-use tauri::State;
+use tauri::{AppHandle, State};
 
+use crate::events::{self, Entity, Operation};
 use crate::AppState;
 
-use super::{Fail, SResult, Seccess};
+use super::{SResult, Seccess};
 
 #[tauri::command]
 pub async fn list_products(state: State<'_, AppState>, args: ListArgs) -> SResult<Value> {
-    let _ = state.db_conn;
-    let res = QueriesService::list_products(&state.db_conn, args).await;
+    let db = state.db()?;
+    let res = QueriesService::list_products(db, args).await;
     match res {
         Ok(res) => Ok(Seccess {
             error: None,
@@ -18,18 +19,15 @@ pub async fn list_products(state: State<'_, AppState>, args: ListArgs) -> SResul
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
 
 #[tauri::command]
 pub async fn search_products(state: State<'_, AppState>, search: String) -> SResult<Vec<Value>> {
-    let _ = state.db_conn;
-    let res = QueriesService::search_products(&state.db_conn, search).await;
+    let db = state.db()?;
+    let res = QueriesService::search_products(db, search).await;
     match res {
         Ok(res) => Ok(Seccess {
             error: None,
@@ -38,18 +36,18 @@ pub async fn search_products(state: State<'_, AppState>, search: String) -> SRes
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
 
 #[tauri::command]
-pub async fn create_product(state: State<'_, AppState>, product: NewProduct) -> SResult<String> {
-    let _ = state.db_conn;
-    let res = MutationsService::create_product(&state.db_conn, product).await;
+pub async fn create_product(app: AppHandle, state: State<'_, AppState>, product: NewProduct) -> SResult<String> {
+    let db = state.db()?;
+    let res = MutationsService::create_product(db, product).await;
+    if let Ok(id) = &res {
+        events::db_change(&app, Entity::Products, Some(id.clone()), Operation::Created);
+    }
     match res {
         Ok(id) => Ok(Seccess::<String> {
             error: None,
@@ -58,18 +56,19 @@ pub async fn create_product(state: State<'_, AppState>, product: NewProduct) ->
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
 
 #[tauri::command]
-pub async fn delete_product(state: State<'_, AppState>, id: String) -> SResult<u64> {
-    let _ = state.db_conn;
-    let res = MutationsService::delete_product(&state.db_conn, id).await;
+pub async fn delete_product(app: AppHandle, state: State<'_, AppState>, id: String) -> SResult<u64> {
+    let db = state.db()?;
+    let deleted = id.clone();
+    let res = MutationsService::delete_product(db, id).await;
+    if matches!(res, Ok(rows) if rows > 0) {
+        events::db_change(&app, Entity::Products, Some(deleted), Operation::Deleted);
+    }
     match res {
         Ok(res) => Ok(Seccess {
             error: None,
@@ -78,18 +77,19 @@ pub async fn delete_product(state: State<'_, AppState>, id: String) -> SResult<u
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
 
 #[tauri::command]
-pub async fn update_product(state: State<'_, AppState>, product: Product) -> SResult<String> {
-    let _ = state.db_conn;
-    let res = MutationsService::update_product(&state.db_conn, product).await;
+pub async fn update_product(app: AppHandle, state: State<'_, AppState>, product: Product) -> SResult<String> {
+    let db = state.db()?;
+    let updated = product.id.clone();
+    let res = MutationsService::update_product(db, product).await;
+    if res.is_ok() {
+        events::db_change(&app, Entity::Products, Some(updated), Operation::Updated);
+    }
     match res {
         Ok(_) => Ok(Seccess::<String> {
             error: None,
@@ -98,10 +98,7 @@ pub async fn update_product(state: State<'_, AppState>, product: Product) -> SRe
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
@@ -1,15 +1,16 @@
 use serde_json::Value;
-use service::{ListArgs, MutationsService, NewQuote, QueriesService, Quote};
-use tauri::State;
+use service::{ListArgs, MutationsService, NewQuote, NewQuoteWithItems, QueriesService, Quote};
+use tauri::{AppHandle, State};
 
+use crate::events::{self, Entity, Operation};
 use crate::AppState;
 
-use super::{Fail, SResult, Seccess};
+use super::{SResult, Seccess};
 
 #[tauri::command]
 pub async fn list_quotes(state: State<'_, AppState>, args: ListArgs) -> SResult<Value> {
-    let _ = state.db_conn;
-    let res = QueriesService::list_quotes(&state.db_conn, args).await;
+    let db = state.db()?;
+    let res = QueriesService::list_quotes(db, args).await;
     match res {
         Ok(res) => Ok(Seccess {
             error: None,
@@ -18,18 +19,15 @@ pub async fn list_quotes(state: State<'_, AppState>, args: ListArgs) -> SResult<
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
 
 #[tauri::command]
 pub async fn list_quote_products(state: State<'_, AppState>, id: String) -> SResult<Vec<Value>> {
-    let _ = state.db_conn;
-    let res = QueriesService::list_quote_products(&state.db_conn, id).await;
+    let db = state.db()?;
+    let res = QueriesService::list_quote_products(db, id).await;
     match res {
         Ok(res) => Ok(Seccess {
             error: None,
@@ -38,18 +36,18 @@ pub async fn list_quote_products(state: State<'_, AppState>, id: String) -> SRes
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
 
 #[tauri::command]
-pub async fn create_quote(state: State<'_, AppState>, quote: NewQuote) -> SResult<String> {
-    let _ = state.db_conn;
-    let res = MutationsService::create_quote(&state.db_conn, quote).await;
+pub async fn create_quote(app: AppHandle, state: State<'_, AppState>, quote: NewQuote) -> SResult<String> {
+    let db = state.db()?;
+    let res = MutationsService::create_quote(db, quote).await;
+    if let Ok(id) = &res {
+        events::db_change(&app, Entity::Quotes, Some(id.clone()), Operation::Created);
+    }
     match res {
         Ok(id) => Ok(Seccess {
             error: None,
@@ -58,18 +56,39 @@ pub async fn create_quote(state: State<'_, AppState>, quote: NewQuote) -> SResul
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
 
 #[tauri::command]
-pub async fn update_quote(state: State<'_, AppState>, quote: Quote) -> SResult<()> {
-    let _ = state.db_conn;
-    let res = MutationsService::update_quote(&state.db_conn, quote).await;
+pub async fn create_quote_with_items(app: AppHandle, state: State<'_, AppState>, quote: NewQuoteWithItems) -> SResult<Value> {
+    let db = state.db()?;
+    let res = MutationsService::create_quote_with_items(db, quote).await;
+    if let Ok(created) = &res {
+        events::document_created(&app, Entity::Quotes, Entity::QuoteItems, created);
+    }
+    match res {
+        Ok(res) => Ok(Seccess {
+            error: None,
+            message: None,
+            data: Some(res),
+        }),
+        Err(err) => {
+            println!("Error: {}", err);
+            Err(err.into())
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn update_quote(app: AppHandle, state: State<'_, AppState>, quote: Quote) -> SResult<()> {
+    let db = state.db()?;
+    let updated = quote.id.clone();
+    let res = MutationsService::update_quote(db, quote).await;
+    if res.is_ok() {
+        events::db_change(&app, Entity::Quotes, Some(updated), Operation::Updated);
+    }
     match res {
         Ok(_) => Ok(Seccess {
             error: None,
@@ -78,18 +97,19 @@ pub async fn update_quote(state: State<'_, AppState>, quote: Quote) -> SResult<(
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
 
 #[tauri::command]
-pub async fn delete_quote(state: State<'_, AppState>, id: String) -> SResult<u64> {
-    let _ = state.db_conn;
-    let res = MutationsService::delete_quote(&state.db_conn, id).await;
+pub async fn delete_quote(app: AppHandle, state: State<'_, AppState>, id: String) -> SResult<u64> {
+    let db = state.db()?;
+    let deleted = id.clone();
+    let res = MutationsService::delete_quote(db, id).await;
+    if matches!(res, Ok(rows) if rows > 0) {
+        events::db_change(&app, Entity::Quotes, Some(deleted), Operation::Deleted);
+    }
     match res {
         Ok(res) => Ok(Seccess {
             error: None,
@@ -98,18 +118,15 @@ pub async fn delete_quote(state: State<'_, AppState>, id: String) -> SResult<u64
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
 
 #[tauri::command]
 pub async fn get_quote(state: State<'_, AppState>, id: String) -> SResult<Value> {
-    let _ = state.db_conn;
-    let res = QueriesService::get_quote(&state.db_conn, id).await;
+    let db = state.db()?;
+    let res = QueriesService::get_quote(db, id).await;
     match res {
         Ok(res) => Ok(Seccess {
             error: None,
@@ -118,18 +135,15 @@ pub async fn get_quote(state: State<'_, AppState>, id: String) -> SResult<Value>
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
 
 #[tauri::command]
 pub async fn get_quote_details(state: State<'_, AppState>, id: String) -> SResult<Value> {
-    let _ = state.db_conn;
-    let res = QueriesService::get_quote_details(&state.db_conn, id).await;
+    let db = state.db()?;
+    let res = QueriesService::get_quote_details(db, id).await;
     match res {
         Ok(res) => Ok(Seccess {
             error: None,
@@ -138,10 +152,7 @@ pub async fn get_quote_details(state: State<'_, AppState>, id: String) -> SResul
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
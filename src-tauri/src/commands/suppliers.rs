@@ -1,15 +1,16 @@
 use serde_json::Value;
 use service::{ListArgs, MutationsService, NewSupplier, QueriesService, Supplier};
-use tauri::State;
+use tauri::{AppHandle, State};
 
+use crate::events::{self, Entity, Operation};
 use crate::AppState;
 
-use super::{Fail, SResult, Seccess};
+use super::{SResult, Seccess};
 
 #[tauri::command]
 pub async fn list_suppliers(state: State<'_, AppState>, args: ListArgs) -> SResult<Value> {
-    let _ = state.db_conn;
-    let res = QueriesService::list_suppliers(&state.db_conn, args).await;
+    let db = state.db()?;
+    let res = QueriesService::list_suppliers(db, args).await;
     match res {
         Ok(res) => Ok(Seccess {
             error: None,
@@ -18,18 +19,15 @@ pub async fn list_suppliers(state: State<'_, AppState>, args: ListArgs) -> SResu
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
 
 #[tauri::command]
 pub async fn search_suppliers(state: State<'_, AppState>, search: String) -> SResult<Vec<Value>> {
-    let _ = state.db_conn;
-    let res = QueriesService::search_suppliers(&state.db_conn, search).await;
+    let db = state.db()?;
+    let res = QueriesService::search_suppliers(db, search).await;
     match res {
         Ok(res) => Ok(Seccess {
             error: None,
@@ -38,18 +36,18 @@ pub async fn search_suppliers(state: State<'_, AppState>, search: String) -> SRe
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
 
 #[tauri::command]
-pub async fn create_supplier(state: State<'_, AppState>, supplier: NewSupplier) -> SResult<String> {
-    let _ = state.db_conn;
-    let res = MutationsService::create_supplier(&state.db_conn, supplier).await;
+pub async fn create_supplier(app: AppHandle, state: State<'_, AppState>, supplier: NewSupplier) -> SResult<String> {
+    let db = state.db()?;
+    let res = MutationsService::create_supplier(db, supplier).await;
+    if let Ok(id) = &res {
+        events::db_change(&app, Entity::Suppliers, Some(id.clone()), Operation::Created);
+    }
     match res {
         Ok(id) => Ok(Seccess::<String> {
             error: None,
@@ -58,18 +56,19 @@ pub async fn create_supplier(state: State<'_, AppState>, supplier: NewSupplier)
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
 
 #[tauri::command]
-pub async fn delete_supplier(state: State<'_, AppState>, id: String) -> SResult<u64> {
-    let _ = state.db_conn;
-    let res = MutationsService::delete_supplier(&state.db_conn, id).await;
+pub async fn delete_supplier(app: AppHandle, state: State<'_, AppState>, id: String) -> SResult<u64> {
+    let db = state.db()?;
+    let deleted = id.clone();
+    let res = MutationsService::delete_supplier(db, id).await;
+    if matches!(res, Ok(rows) if rows > 0) {
+        events::db_change(&app, Entity::Suppliers, Some(deleted), Operation::Deleted);
+    }
     match res {
         Ok(res) => Ok(Seccess {
             error: None,
@@ -78,18 +77,19 @@ pub async fn delete_supplier(state: State<'_, AppState>, id: String) -> SResult<
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
 
 #[tauri::command]
-pub async fn update_supplier(state: State<'_, AppState>, supplier: Supplier) -> SResult<String> {
-    let _ = state.db_conn;
-    let res = MutationsService::update_supplier(&state.db_conn, supplier).await;
+pub async fn update_supplier(app: AppHandle, state: State<'_, AppState>, supplier: Supplier) -> SResult<String> {
+    let db = state.db()?;
+    let updated = supplier.id.clone();
+    let res = MutationsService::update_supplier(db, supplier).await;
+    if res.is_ok() {
+        events::db_change(&app, Entity::Suppliers, Some(updated), Operation::Updated);
+    }
     match res {
         Ok(_) => Ok(Seccess::<String> {
             error: None,
@@ -98,10 +98,7 @@ pub async fn update_supplier(state: State<'_, AppState>, supplier: Supplier) ->
         }),
         Err(err) => {
             println!("Error: {}", err);
-            Err(Fail {
-                error: Some(err.to_string()),
-                message: None,
-            })
+            Err(err.into())
         }
     }
 }
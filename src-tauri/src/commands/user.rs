@@ -1,89 +1,204 @@
-use serde::{Deserialize, Serialize};
+use reqwest::Url;
 use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
 use tauri::State;
-use reqwest::Client;
 
-use crate::AppState;
+use crate::error::AppError;
+use crate::http::HttpState;
+use crate::session::{extract_tokens, strip_tokens, SessionState};
+use crate::signing::{self, SigningKeys, SigningState};
 
-// Define the login request payload
-#[derive(Serialize)]
-struct LoginRequest {
+use super::{SResult, Seccess};
+
+const LOGIN_PATH: &str = "ums/user/login-by-username";
+
+// 登录并保存会话令牌，返回后端的用户信息（不含令牌）
+pub async fn login(
+    http: &HttpState,
+    keys: &SigningKeys,
+    session: &SessionState,
     username: String,
     password: String,
-}
+) -> Result<Value, AppError> {
+    let base_url = http.settings().base_url;
+    let url = Url::parse(&base_url)
+        .and_then(|base| base.join(LOGIN_PATH))
+        .map_err(|e| AppError::Validation(format!("Invalid base url {}: {}", base_url, e)))?;
 
-// Define the login response structure
-#[derive(Deserialize)]
-struct LoginResponse {
-    token: Option<String>,
-    error: Option<String>,
-    message: Option<String>,
-}
+    // 与其他接口一致，表单参数附带 ts 和 sn 签名
+    let mut params = BTreeMap::from([
+        ("username".to_string(), username),
+        ("password".to_string(), password),
+    ]);
+    keys.sign_params(&mut params, signing::timestamp());
+    let form: HashMap<String, String> = params.into_iter().collect();
 
-// Define the success and failure response wrappers
-#[derive(Serialize)]
-pub struct SResult<T> {
-    error: Option<String>,
-    message: Option<String>,
-    data: Option<T>,
-}
+    let response = http.with_read_timeout(http.client().post(url).form(&form).send()).await?;
+    let status = response.status();
+    let body = http.with_read_timeout(response.text()).await?;
+    if !status.is_success() {
+        return Err(AppError::Auth(format!("Failed to login: Status code: {} - {}", status, body)));
+    }
 
-#[derive(Serialize)]
-pub struct Fail {
-    error: Option<String>,
-    message: Option<String>,
+    let mut res: Value = serde_json::from_str(&body)
+        .map_err(|_| AppError::Auth("Failed to parse response".to_string()))?;
+    match extract_tokens(&res) {
+        Some(tokens) => {
+            session.set(Some(tokens));
+            strip_tokens(&mut res);
+            Ok(res)
+        }
+        None => {
+            let message = ["message", "error"]
+                .iter()
+                .find_map(|key| res.get(*key).and_then(Value::as_str))
+                .unwrap_or("Unknown error occurred");
+            Err(AppError::Auth(message.to_string()))
+        }
+    }
 }
 
 // The login command
 #[tauri::command]
 pub async fn login_by_username(
-    state: State<'_, AppState>,
+    http: State<'_, HttpState>,
+    signing: State<'_, SigningState>,
+    session: State<'_, SessionState>,
     username: String,
     password: String,
 ) -> SResult<Value> {
-    let client = Client::new();
-    
-    // Create the login request payload
-    let login_request = LoginRequest { username, password };
-    
-    // Make the API request
-    match client
-        .post("https://your-backend-url.com/api/ums/user/login-by-username")
-        .json(&login_request)
-        .send()
-        .await
-    {
-        Ok(response) => {
-            if response.status().is_success() {
-                // Parse the JSON response
-                let res: LoginResponse = response.json().await.unwrap_or_else(|_| LoginResponse {
-                    token: None,
-                    error: Some("Failed to parse response".to_string()),
-                    message: None,
-                });
-                
-                if let Some(token) = res.token {
-                    Ok(SResult {
-                        error: None,
-                        message: Some("Login successful".to_string()),
-                        data: Some(Value::String(token)),
-                    })
-                } else {
-                    Err(Fail {
-                        error: res.error.or(Some("Unknown error occurred".to_string())),
-                        message: res.message,
-                    })
-                }
-            } else {
-                Err(Fail {
-                    error: Some("Failed to login".to_string()),
-                    message: None,
-                })
-            }
-        }
-        Err(err) => Err(Fail {
-            error: Some(err.to_string()),
-            message: None,
+    let res = match signing.keys() {
+        Ok(keys) => login(&http, &keys, &session, username, password).await,
+        Err(err) => Err(err),
+    };
+    match res {
+        Ok(res) => Ok(Seccess {
+            error: None,
+            message: Some("Login successful".to_string()),
+            data: Some(res),
         }),
+        Err(err) => {
+            println!("Error: {}", err);
+            Err(err)
+        }
+    }
+}
+
+#[tauri::command]
+pub fn logout(session: State<'_, SessionState>) -> SResult<()> {
+    session.set(None);
+    Ok(Seccess {
+        error: None,
+        message: Some("Logout successful".to_string()),
+        data: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::HttpSettings;
+    use serde_json::json;
+    use wiremock::matchers::{body_string_contains, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn setup(server: &MockServer) -> (HttpState, SessionState) {
+        let http = HttpState::load(None);
+        http.update(HttpSettings {
+            base_url: format!("{}/api/", server.uri()),
+            ..Default::default()
+        })
+        .unwrap();
+        (http, SessionState::load(None))
+    }
+
+    #[tokio::test]
+    async fn login_stores_tokens() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/ums/user/login-by-username"))
+            .and(body_string_contains("username=admin"))
+            .and(body_string_contains("sn="))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "token": "access-1",
+                "refresh_token": "refresh-1",
+                "username": "admin",
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let (http, session) = setup(&server).await;
+
+        let res = login(&http, &SigningKeys::default(), &session, "admin".into(), "secret".into())
+            .await
+            .unwrap();
+
+        assert_eq!(res["username"], "admin");
+        assert!(res.get("token").is_none());
+        assert!(res.get("refresh_token").is_none());
+        let tokens = session.tokens().unwrap();
+        assert_eq!(tokens.token, "access-1");
+        assert_eq!(tokens.refresh_token.as_deref(), Some("refresh-1"));
+    }
+
+    #[tokio::test]
+    async fn login_reads_nested_data() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/ums/user/login-by-username"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "code": 0,
+                "data": { "token": "access-2" },
+            })))
+            .mount(&server)
+            .await;
+        let (http, session) = setup(&server).await;
+
+        let res = login(&http, &SigningKeys::default(), &session, "admin".into(), "secret".into())
+            .await
+            .unwrap();
+
+        assert_eq!(session.access_token().as_deref(), Some("access-2"));
+        assert_eq!(res["code"], 0);
+        assert!(res["data"].get("token").is_none());
+    }
+
+    #[tokio::test]
+    async fn login_rejected_without_token() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/ums/user/login-by-username"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "code": 400001,
+                "message": "wrong password",
+            })))
+            .mount(&server)
+            .await;
+        let (http, session) = setup(&server).await;
+
+        let err = login(&http, &SigningKeys::default(), &session, "admin".into(), "bad".into())
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.code(), "AUTH");
+        assert_eq!(err.message(), "wrong password");
+        assert!(session.tokens().is_none());
+    }
+
+    #[tokio::test]
+    async fn login_fails_on_http_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(401).set_body_string("unauthorized"))
+            .mount(&server)
+            .await;
+        let (http, session) = setup(&server).await;
+
+        let err = login(&http, &SigningKeys::default(), &session, "admin".into(), "secret".into())
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.code(), "AUTH");
+        assert!(session.tokens().is_none());
     }
 }
@@ -1,7 +1,12 @@
 use log::{info, warn, error}; // 你可以根据需要使用不同级别的日志
+use tauri::State;
+
+use crate::http::HttpState;
 
 #[tauri::command]
-pub fn log_message(level: String, message: String) {
+pub fn log_message(http: State<'_, HttpState>, level: String, message: String) {
+    // 前端日志同样脱敏后再写入
+    let message = http.settings().log_redaction.text(&message);
     match level.as_str() {
         "info" => info!("{}", message),
         "warn" => warn!("{}", message),
@@ -1,5 +1,6 @@
 use migration::sea_orm::Database;
 use migration::sea_orm::DatabaseConnection;
+use migration::sea_orm::DbErr;
 
 #[cfg(not(debug_assertions))]
 use std::fs;
@@ -9,30 +10,28 @@ use dotenvy::dotenv;
 #[cfg(debug_assertions)]
 use std::env;
 
-pub async fn establish_connection() -> DatabaseConnection {
+pub async fn establish_connection() -> Result<DatabaseConnection, DbErr> {
     #[cfg(debug_assertions)]
     dotenv().ok();
 
     #[cfg(debug_assertions)]
-    let db_url = env::var("DATABASE_URL").expect("DATABASE_URL is missing check .env file in src-taur");
+    let db_url = env::var("DATABASE_URL")
+        .map_err(|_| DbErr::Custom("DATABASE_URL is missing check .env file in src-taur".to_string()))?;
 
     #[cfg(not(debug_assertions))]
-    let home_dir = match tauri::api::path::data_dir() {
-        Some(val) => val,
-        None => panic!("Could not get home directory"),
-    };
+    let home_dir = tauri::api::path::data_dir()
+        .ok_or_else(|| DbErr::Custom("Could not get home directory".to_string()))?;
 
     #[cfg(not(debug_assertions))]
     let data_dir = home_dir.join(".mahalli/data");
     #[cfg(not(debug_assertions))]
-    if let Err(_) = fs::metadata(&data_dir) {
-        fs::create_dir_all(&data_dir).expect("Could not create data directory");
+    if fs::metadata(&data_dir).is_err() {
+        fs::create_dir_all(&data_dir)
+            .map_err(|e| DbErr::Custom(format!("Could not create data directory: {}", e)))?;
     }
 
     #[cfg(not(debug_assertions))]
-    let db_url = "sqlite://".to_string() + data_dir.to_str().unwrap() + "/db.sqlite?mode=rwc";
+    let db_url = "sqlite://".to_string() + &data_dir.to_string_lossy() + "/db.sqlite?mode=rwc";
 
-    Database::connect(&db_url)
-        .await
-        .expect(&format!("Error connecting to {}", &db_url))
+    Database::connect(&db_url).await
 }
@@ -0,0 +1,80 @@
+use serde::Serialize;
+use service::sea_orm::{DbErr, SqlErr};
+use std::fmt;
+
+use crate::websocket::protocol::ProtocolError;
+
+// 所有 Tauri 命令共用的错误类型。
+// 序列化为 { "code": "NOT_FOUND", "message": "..." }，前端按 code 区分处理。
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "code", content = "message", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AppError {
+    NotFound(String),
+    Validation(String),
+    Conflict(String),
+    Database(String),
+    Network(String),
+    Auth(String),
+    #[serde(rename = "WEBSOCKET")]
+    WebSocket(String),
+}
+
+impl AppError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::NotFound(_) => "NOT_FOUND",
+            AppError::Validation(_) => "VALIDATION",
+            AppError::Conflict(_) => "CONFLICT",
+            AppError::Database(_) => "DATABASE",
+            AppError::Network(_) => "NETWORK",
+            AppError::Auth(_) => "AUTH",
+            AppError::WebSocket(_) => "WEBSOCKET",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            AppError::NotFound(message)
+            | AppError::Validation(message)
+            | AppError::Conflict(message)
+            | AppError::Database(message)
+            | AppError::Network(message)
+            | AppError::Auth(message)
+            | AppError::WebSocket(message) => message,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}", self.code(), self.message())
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<DbErr> for AppError {
+    fn from(err: DbErr) -> Self {
+        match err.sql_err() {
+            Some(SqlErr::UniqueConstraintViolation(message)) => return AppError::Conflict(message),
+            Some(SqlErr::ForeignKeyConstraintViolation(message)) => return AppError::Conflict(message),
+            _ => {}
+        }
+        match err {
+            DbErr::RecordNotFound(message) => AppError::NotFound(message),
+            err => AppError::Database(err.to_string()),
+        }
+    }
+}
+
+impl From<reqwest::Error> for AppError {
+    fn from(err: reqwest::Error) -> Self {
+        AppError::Network(err.to_string())
+    }
+}
+
+impl From<ProtocolError> for AppError {
+    fn from(err: ProtocolError) -> Self {
+        AppError::Validation(err.to_string())
+    }
+}
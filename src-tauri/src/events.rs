@@ -0,0 +1,146 @@
+use log::error;
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Manager};
+
+use crate::hub::{self, HubState};
+
+// 推送给前端的事件出口；应用中由 AppHandle 广播，测试中可以记录下来检查
+pub trait EventSink: Send + Sync + 'static {
+    fn emit_value(&self, event: &str, payload: Value);
+}
+
+impl EventSink for AppHandle {
+    fn emit_value(&self, event: &str, payload: Value) {
+        // 数据变更和任务进度同时推送给本机 hub 的客户端
+        if hub::is_forwarded(event) {
+            if let Some(hub) = self.try_state::<HubState>() {
+                hub.publish(event, &payload);
+            }
+        }
+        if let Err(e) = self.emit_all(event, payload) {
+            error!("Failed to emit {}: {}", event, e);
+        }
+    }
+}
+
+pub fn emit<E: EventSink + ?Sized, S: Serialize>(sink: &E, event: &str, payload: S) {
+    match serde_json::to_value(payload) {
+        Ok(payload) => sink.emit_value(event, payload),
+        Err(e) => error!("Failed to serialize {} payload: {}", event, e),
+    }
+}
+
+// 数据库写入成功后广播，前端据此刷新列表、仪表盘等页面
+pub const EVENT_DB_CHANGE: &str = "db://change";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Entity {
+    Products,
+    Inventory,
+    Clients,
+    Suppliers,
+    Orders,
+    OrderItems,
+    Quotes,
+    QuoteItems,
+    Invoices,
+    InvoiceItems,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+    Created,
+    Updated,
+    Deleted,
+    // CSV 批量导入，id 为空
+    Imported,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct DbChange {
+    pub entity: Entity,
+    pub id: Option<String>,
+    pub operation: Operation,
+}
+
+pub fn db_change<E: EventSink + ?Sized>(sink: &E, entity: Entity, id: Option<String>, operation: Operation) {
+    emit(sink, EVENT_DB_CHANGE, DbChange { entity, id, operation });
+}
+
+// 带明细创建的单据同时写入了明细行和出库记录，逐条通知，监听明细或库存的页面也能刷新
+pub fn document_created<E: EventSink + ?Sized>(sink: &E, entity: Entity, item_entity: Entity, created: &Value) {
+    let id = |value: &Value| value.as_str().map(str::to_string);
+    db_change(sink, entity, id(&created["id"]), Operation::Created);
+    for item in created["items"].as_array().into_iter().flatten() {
+        db_change(sink, item_entity, id(&item["id"]), Operation::Created);
+        if let Some(inventory_id) = id(&item["inventory_id"]) {
+            db_change(sink, Entity::Inventory, Some(inventory_id), Operation::Created);
+        }
+    }
+}
+
+// 测试用：按顺序记录所有事件
+#[cfg(test)]
+#[derive(Default)]
+pub struct RecordedEvents {
+    events: std::sync::Mutex<Vec<(String, Value)>>,
+}
+
+#[cfg(test)]
+impl RecordedEvents {
+    pub fn named(&self, event: &str) -> Vec<Value> {
+        self.events
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(name, _)| name == event)
+            .map(|(_, payload)| payload.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+impl EventSink for RecordedEvents {
+    fn emit_value(&self, event: &str, payload: Value) {
+        self.events.lock().unwrap().push((event.to_string(), payload));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn document_created_announces_items_and_inventory() {
+        let events = RecordedEvents::default();
+        let created = json!({
+            "id": "o1",
+            "items": [
+                { "id": "i1", "inventory_id": "m1" },
+                { "id": "i2", "inventory_id": "m2" },
+            ],
+        });
+
+        document_created(&events, Entity::Orders, Entity::OrderItems, &created);
+
+        let changes: Vec<(Value, Value)> = events
+            .named(EVENT_DB_CHANGE)
+            .into_iter()
+            .map(|change| (change["entity"].clone(), change["id"].clone()))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                (json!("orders"), json!("o1")),
+                (json!("order_items"), json!("i1")),
+                (json!("inventory"), json!("m1")),
+                (json!("order_items"), json!("i2")),
+                (json!("inventory"), json!("m2")),
+            ]
+        );
+    }
+}
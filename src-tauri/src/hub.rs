@@ -0,0 +1,320 @@
+use base64::Engine;
+use futures_util::{SinkExt, StreamExt};
+use log::{error, info};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
+use tauri::State;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::{watch, Mutex};
+use tokio::task::JoinHandle;
+use tokio_tungstenite::accept_hdr_async;
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::{header::AUTHORIZATION, StatusCode};
+use tokio_tungstenite::tungstenite::protocol::Message;
+
+use crate::error::AppError;
+
+pub const DEFAULT_HUB_PORT: u16 = 17890;
+// 端口和令牌写入数据目录，供本机脚本读取
+const HUB_FILE: &str = "hub.json";
+// 客户端读取过慢时最多缓存的事件数，超出后丢弃最早的事件并通知客户端
+const HUB_BUFFER: usize = 256;
+
+// 转发给 hub 客户端的事件前缀：数据库变更、WebSocket 任务消息和前端发布的任务进度
+const FORWARDED_EVENTS: [&str; 3] = ["db://", "ws://", "task://"];
+
+pub fn is_forwarded(event: &str) -> bool {
+    FORWARDED_EVENTS.iter().any(|prefix| event.starts_with(prefix))
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct HubInfo {
+    pub running: bool,
+    pub url: Option<String>,
+    pub port: Option<u16>,
+    pub token: Option<String>,
+    pub clients: usize,
+}
+
+struct RunningHub {
+    addr: SocketAddr,
+    shutdown: watch::Sender<bool>,
+    task: JoinHandle<()>,
+}
+
+// 本机事件中心：只监听回环地址，客户端需携带每次启动生成的令牌
+pub struct HubState {
+    token: String,
+    events: broadcast::Sender<String>,
+    server: Mutex<Option<RunningHub>>,
+    info_path: Option<PathBuf>,
+}
+
+impl HubState {
+    pub fn new(data_dir: Option<PathBuf>) -> Self {
+        let bytes: [u8; 32] = rand::random();
+        let (events, _) = broadcast::channel(HUB_BUFFER);
+        Self {
+            token: base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes),
+            events,
+            server: Mutex::new(None),
+            info_path: data_dir.map(|dir| dir.join(HUB_FILE)),
+        }
+    }
+
+    // 没有客户端时直接丢弃
+    pub fn publish(&self, topic: &str, data: &Value) {
+        if self.events.receiver_count() == 0 {
+            return;
+        }
+        let frame = json!({ "topic": topic, "data": data, "ts": chrono::Utc::now().timestamp_millis() });
+        let _ = self.events.send(frame.to_string());
+    }
+
+    pub async fn start(&self, port: u16) -> Result<HubInfo, AppError> {
+        let mut server = self.server.lock().await;
+        if let Some(running) = server.as_ref() {
+            if !running.task.is_finished() {
+                return Ok(self.info(Some(running.addr)));
+            }
+        }
+
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))
+            .await
+            .map_err(|e| AppError::WebSocket(format!("Could not listen on 127.0.0.1:{}: {}", port, e)))?;
+        let addr = listener
+            .local_addr()
+            .map_err(|e| AppError::WebSocket(e.to_string()))?;
+        let (shutdown, shutdown_rx) = watch::channel(false);
+        let task = tokio::spawn(serve(listener, self.token.clone(), self.events.clone(), shutdown_rx));
+        *server = Some(RunningHub { addr, shutdown, task });
+        info!("Local event hub listening on {}", addr);
+
+        let info = self.info(Some(addr));
+        self.write_info(&info);
+        Ok(info)
+    }
+
+    pub async fn stop(&self) -> bool {
+        let running = self.server.lock().await.take();
+        if let Some(path) = &self.info_path {
+            let _ = fs::remove_file(path);
+        }
+        match running {
+            Some(running) => {
+                let _ = running.shutdown.send(true);
+                let _ = running.task.await;
+                info!("Local event hub on {} stopped.", running.addr);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub async fn status(&self) -> HubInfo {
+        let server = self.server.lock().await;
+        self.info(server.as_ref().filter(|running| !running.task.is_finished()).map(|running| running.addr))
+    }
+
+    fn info(&self, addr: Option<SocketAddr>) -> HubInfo {
+        match addr {
+            Some(addr) => HubInfo {
+                running: true,
+                url: Some(format!("ws://{}", addr)),
+                port: Some(addr.port()),
+                token: Some(self.token.clone()),
+                clients: self.events.receiver_count(),
+            },
+            None => HubInfo::default(),
+        }
+    }
+
+    fn write_info(&self, info: &HubInfo) {
+        let Some(path) = &self.info_path else { return };
+        let written = serde_json::to_string_pretty(info)
+            .map_err(|e| e.to_string())
+            .and_then(|text| {
+                if let Some(dir) = path.parent() {
+                    fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+                }
+                fs::write(path, text).map_err(|e| e.to_string())?;
+                // 令牌只允许当前用户读取
+                #[cfg(unix)]
+                {
+                    use std::os::unix::fs::PermissionsExt;
+                    fs::set_permissions(path, fs::Permissions::from_mode(0o600)).map_err(|e| e.to_string())?;
+                }
+                Ok(())
+            });
+        if let Err(e) = written {
+            error!("Failed to write {}: {}", path.display(), e);
+        }
+    }
+}
+
+async fn serve(listener: TcpListener, token: String, events: broadcast::Sender<String>, mut shutdown: watch::Receiver<bool>) {
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((tcp, peer)) => {
+                    tokio::spawn(handle_client(tcp, peer, token.clone(), events.subscribe(), shutdown.clone()));
+                }
+                Err(e) => error!("Local event hub accept failed: {}", e),
+            },
+            _ = shutdown.changed() => break,
+        }
+    }
+}
+
+// 浏览器无法自定义 WebSocket 请求头，令牌也可以放在查询参数 ?token= 中
+fn authorized(request: &Request, token: &str) -> bool {
+    let from_query = request.uri().query().and_then(|query| {
+        serde_urlencoded::from_str::<HashMap<String, String>>(query)
+            .ok()?
+            .remove("token")
+    });
+    let from_header = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::to_string);
+    from_query.or(from_header).is_some_and(|candidate| constant_time_eq(candidate.as_bytes(), token.as_bytes()))
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+async fn handle_client(
+    tcp: TcpStream,
+    peer: SocketAddr,
+    token: String,
+    mut events: broadcast::Receiver<String>,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    // 回调签名由 tungstenite 决定
+    #[allow(clippy::result_large_err)]
+    let callback = |request: &Request, response: Response| -> Result<Response, ErrorResponse> {
+        if authorized(request, &token) {
+            Ok(response)
+        } else {
+            let mut rejection = ErrorResponse::new(Some("Invalid hub token.".to_string()));
+            *rejection.status_mut() = StatusCode::UNAUTHORIZED;
+            Err(rejection)
+        }
+    };
+    let stream = match accept_hdr_async(tcp, callback).await {
+        Ok(stream) => stream,
+        Err(e) => {
+            info!("Rejected hub client {}: {}", peer, e);
+            return;
+        }
+    };
+    info!("Hub client {} connected.", peer);
+
+    let (mut sink, mut source) = stream.split();
+    loop {
+        tokio::select! {
+            event = events.recv() => {
+                let text = match event {
+                    Ok(text) => text,
+                    Err(RecvError::Lagged(missed)) => json!({ "topic": "hub://lagged", "data": { "missed": missed } }).to_string(),
+                    Err(RecvError::Closed) => break,
+                };
+                if sink.send(Message::Text(text)).await.is_err() {
+                    break;
+                }
+            }
+            frame = source.next() => match frame {
+                // hub 只向客户端推送，客户端消息忽略；Ping 由 tungstenite 自动回复
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+            _ = shutdown.changed() => {
+                let _ = sink.send(Message::Close(None)).await;
+                break;
+            }
+        }
+    }
+    info!("Hub client {} disconnected.", peer);
+}
+
+#[tauri::command]
+pub async fn start_websocket_server(hub: State<'_, HubState>, port: Option<u16>) -> Result<HubInfo, AppError> {
+    hub.start(port.unwrap_or(DEFAULT_HUB_PORT)).await
+}
+
+#[tauri::command]
+pub async fn stop_websocket_server(hub: State<'_, HubState>) -> Result<bool, AppError> {
+    Ok(hub.stop().await)
+}
+
+#[tauri::command]
+pub async fn get_websocket_server_status(hub: State<'_, HubState>) -> Result<HubInfo, AppError> {
+    Ok(hub.status().await)
+}
+
+// 前端把任务进度等事件发布给 hub 客户端
+#[tauri::command]
+pub fn publish_hub_event(hub: State<'_, HubState>, topic: String, data: Value) -> Result<(), AppError> {
+    if !is_forwarded(&topic) {
+        return Err(AppError::Validation(format!(
+            "Hub topics must start with one of: {}.",
+            FORWARDED_EVENTS.join(", ")
+        )));
+    }
+    hub.publish(&topic, &data);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio_tungstenite::connect_async;
+
+    const WAIT: Duration = Duration::from_secs(5);
+
+    #[tokio::test]
+    async fn broadcasts_to_authorized_clients() {
+        let hub = HubState::new(None);
+        let info = hub.start(0).await.unwrap();
+        let url = info.url.unwrap();
+        assert!(url.starts_with("ws://127.0.0.1:"));
+
+        let err = connect_async(format!("{}/?token=wrong", url)).await.unwrap_err();
+        assert!(err.to_string().contains("401"), "{}", err);
+
+        let (mut client, _) = connect_async(format!("{}/?token={}", url, info.token.unwrap())).await.unwrap();
+        // 等待服务端完成订阅
+        let deadline = tokio::time::Instant::now() + WAIT;
+        while hub.status().await.clients == 0 {
+            assert!(tokio::time::Instant::now() < deadline);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        hub.publish("db://change", &json!({ "entity": "products" }));
+        let frame = tokio::time::timeout(WAIT, client.next()).await.unwrap().unwrap().unwrap();
+        let frame: Value = serde_json::from_str(frame.to_text().unwrap()).unwrap();
+        assert_eq!(frame["topic"], "db://change");
+        assert_eq!(frame["data"]["entity"], "products");
+
+        assert!(hub.stop().await);
+        assert!(!hub.status().await.running);
+        let closed = tokio::time::timeout(WAIT, client.next()).await.unwrap();
+        assert!(matches!(closed, Some(Ok(Message::Close(_))) | None));
+    }
+
+    #[test]
+    fn forwards_only_hub_topics() {
+        assert!(is_forwarded("db://change"));
+        assert!(is_forwarded("task://progress"));
+        assert!(!is_forwarded("session://expired"));
+    }
+}
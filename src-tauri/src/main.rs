@@ -2,19 +2,45 @@
 
 mod commands;
 mod db;
+mod error;
+mod events;
 mod api; // 导入user模块
+mod http;
+mod hub;
+mod redact;
+mod session;
+mod signing;
+mod vault;
 mod websocket; // 新增 websocket 模块
 mod consoleLog; // 新增 log 模块
+mod startup;
 
 use db::establish_connection;
+use error::AppError;
+use http::HttpState;
+use hub::HubState;
+use session::SessionState;
+use signing::SigningState;
+use vault::VaultState;
 use migration::{Migrator, MigratorTrait};
 use service::sea_orm::DatabaseConnection;
+use startup::{StartupError, StartupState};
+use tauri::Manager;
 use tauri_plugin_log::LogTarget;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+// 连接或迁移失败时 db_conn 为 None，数据库命令返回错误而不是因缺少 State 而 panic
 pub struct AppState {
-    db_conn: DatabaseConnection,
+    db_conn: Option<DatabaseConnection>,
+}
+
+impl AppState {
+    pub fn db(&self) -> Result<&DatabaseConnection, AppError> {
+        self.db_conn
+            .as_ref()
+            .ok_or_else(|| AppError::Database("database unavailable".to_string()))
+    }
 }
 
 #[cfg(debug_assertions)]
@@ -25,16 +51,45 @@ const LOG_TARGETS: [LogTarget; 1] = [LogTarget::LogDir];
 
 #[tokio::main]
 async fn main() {
-    // establish conn
-    let db_conn = establish_connection().await;
-    // run migrations
-    Migrator::up(&db_conn, None).await.unwrap();
+    // establish conn & run migrations
+    // 失败时不再 panic，记录错误并交给前端展示启动错误页
+    let mut startup_state = StartupState::default();
+    let db_conn = match establish_connection().await {
+        Ok(db_conn) => match Migrator::up(&db_conn, None).await {
+            Ok(()) => Some(db_conn),
+            Err(err) => {
+                startup_state.error = Some(StartupError { stage: "migration", error: err.into() });
+                None
+            }
+        },
+        Err(err) => {
+            startup_state.error = Some(StartupError { stage: "connection", error: err.into() });
+            None
+        }
+    };
+    if let Some(startup_error) = &startup_state.error {
+        eprintln!("Startup failed during {}: {}", startup_error.stage, startup_error.error);
+    }
     // Shared state for WebSocket connection
     let websocket_state = Arc::new(Mutex::new(websocket::WebSocketState::default()));
     //
     tauri::Builder::default()
         .manage(AppState { db_conn })
+        .manage(startup_state)
         .manage(websocket_state.clone())
+        // 凭据保险箱，解锁前只能看到账号列表
+        .manage(VaultState::default())
+        .setup(|app| {
+            // 共享 HTTP 客户端，配置保存在应用配置目录
+            app.manage(HttpState::load(app.path_resolver().app_config_dir()));
+            // 请求签名密钥，保存在加密的 signing.enc 中
+            app.manage(SigningState::load(app.path_resolver().app_config_dir()));
+            // 登录会话（访问令牌、刷新令牌），加密保存在 session.enc 中
+            app.manage(SessionState::load(app.path_resolver().app_data_dir()));
+            // 本机事件中心，令牌每次启动重新生成
+            app.manage(HubState::new(app.path_resolver().app_data_dir()));
+            Ok(())
+        })
         .plugin(
             tauri_plugin_log::Builder::default()
                 .targets(LOG_TARGETS)
@@ -66,6 +121,7 @@ async fn main() {
             // clients
             //
             commands::clients::list_clients,
+            commands::clients::get_client,
             commands::clients::search_clients,
             commands::clients::create_client,
             commands::clients::update_client,
@@ -85,6 +141,7 @@ async fn main() {
             commands::orders::get_order,
             commands::orders::get_order_details,
             commands::orders::create_order,
+            commands::orders::create_order_with_items,
             commands::orders::update_order,
             commands::orders::delete_order,
             commands::orders::list_order_products,
@@ -101,6 +158,7 @@ async fn main() {
             commands::quotes::get_quote,
             commands::quotes::get_quote_details,
             commands::quotes::create_quote,
+            commands::quotes::create_quote_with_items,
             commands::quotes::update_quote,
             commands::quotes::delete_quote,
             commands::quotes::list_quote_products,
@@ -117,6 +175,7 @@ async fn main() {
             commands::invoices::get_invoice,
             commands::invoices::get_invoice_details,
             commands::invoices::create_invoice,
+            commands::invoices::create_invoice_with_items,
             commands::invoices::update_invoice,
             commands::invoices::delete_invoice,
             commands::invoices::list_invoice_products,
@@ -136,13 +195,51 @@ async fn main() {
             commands::dashboard::list_status_count,
             commands::dashboard::list_revenue,
             commands::dashboard::list_expenses,
+            //
+            // import / export
+            //
+            commands::import::upload_csv_to_db,
+            commands::export::export_list_to_file,
 
             // 登录
+            commands::user::login_by_username,
+            commands::user::logout,
             api::api_request,
+            http::get_http_settings,
+            http::update_http_settings,
+            signing::update_signing_keys,
+            session::get_session_status,
+            //
+            // credentials
+            //
+            vault::get_vault_status,
+            vault::unlock_vault,
+            vault::lock_vault,
+            vault::store_secret,
+            vault::read_secret,
+            vault::list_secrets,
+            vault::delete_secret,
+            vault::rotate_vault_passphrase,
+            vault::wipe_secrets,
             websocket::start_websocket_connection,
             websocket::send_websocket_message,
             websocket::close_websocket_connection,
+            websocket::get_websocket_status,
+            websocket::list_websocket_sessions,
+            websocket::websocket_request,
+            //
+            // local event hub
+            //
+            hub::start_websocket_server,
+            hub::stop_websocket_server,
+            hub::get_websocket_server_status,
+            hub::publish_hub_event,
             consoleLog::log_message,
+            //
+            // startup
+            //
+            startup::get_startup_error,
+            startup::restart_app,
 
         ])
         .run(tauri::generate_context!())
@@ -0,0 +1,214 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+const MASK: &str = "***";
+
+// 写日志前的脱敏规则，保存在 http-settings.json 的 log_redaction 中
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LogRedaction {
+    // 不区分大小写；`token` 同时匹配 `refresh_token`、`x-access-token` 等以它结尾的字段
+    pub sensitive_keys: Vec<String>,
+    // 请求体、响应体和消息在日志中保留的最大字符数，0 表示不截断
+    pub max_body_length: usize,
+}
+
+impl Default for LogRedaction {
+    fn default() -> Self {
+        Self {
+            sensitive_keys: ["authorization", "cookie", "set-cookie", "token", "password", "secret", "sn"]
+                .iter()
+                .map(|key| key.to_string())
+                .collect(),
+            max_body_length: 2048,
+        }
+    }
+}
+
+fn normalize(key: &str) -> String {
+    key.trim().to_ascii_lowercase().replace('-', "_")
+}
+
+fn is_key_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-'
+}
+
+impl LogRedaction {
+    pub fn is_sensitive(&self, key: &str) -> bool {
+        let key = normalize(key);
+        self.sensitive_keys.iter().map(|sensitive| normalize(sensitive)).any(|sensitive| {
+            !sensitive.is_empty()
+                && (key == sensitive || key.ends_with(&format!("_{}", sensitive)))
+        })
+    }
+
+    // 请求头、查询参数等键值对
+    pub fn pairs<'a, I>(&self, pairs: I) -> BTreeMap<String, String>
+    where
+        I: IntoIterator<Item = (&'a String, &'a String)>,
+    {
+        pairs
+            .into_iter()
+            .map(|(key, value)| {
+                let value = if self.is_sensitive(key) { MASK.to_string() } else { self.text(value) };
+                (key.clone(), value)
+            })
+            .collect()
+    }
+
+    // 递归处理 JSON 对象，敏感字段的值替换为 ***
+    pub fn value(&self, value: &Value) -> Value {
+        match value {
+            Value::Object(object) => Value::Object(
+                object
+                    .iter()
+                    .map(|(key, value)| {
+                        let value = if self.is_sensitive(key) {
+                            Value::String(MASK.to_string())
+                        } else {
+                            self.value(value)
+                        };
+                        (key.clone(), value)
+                    })
+                    .collect(),
+            ),
+            Value::Array(items) => Value::Array(items.iter().map(|item| self.value(item)).collect()),
+            value => value.clone(),
+        }
+    }
+
+    pub fn json(&self, value: &Value) -> String {
+        self.truncate(self.value(value).to_string())
+    }
+
+    // 任意文本：能解析为 JSON 的按字段处理，否则遮盖 `key=value`、`key: value` 和 `Bearer xxx`（URL、表单、Cookie、错误信息、日志文本）
+    pub fn text(&self, text: &str) -> String {
+        let trimmed = text.trim_start();
+        if trimmed.starts_with('{') || trimmed.starts_with('[') {
+            if let Ok(value) = serde_json::from_str::<Value>(text) {
+                return self.json(&value);
+            }
+        }
+        self.truncate(self.mask_assignments(&mask_bearer(text)))
+    }
+
+    // 分隔符前后允许空格；`Authorization: Bearer xxx` 已由 mask_bearer 处理
+    fn mask_assignments(&self, text: &str) -> String {
+        let mut out = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(index) = rest.find(['=', ':']) {
+            let (before, after) = rest.split_at(index + 1);
+            out.push_str(before);
+            let key = before[..index].trim_end();
+            let key_start = key.rfind(|c: char| !is_key_char(c)).map_or(0, |i| i + 1);
+            let value = after.trim_start();
+            if self.is_sensitive(&key[key_start..]) && !value.starts_with("Bearer ") {
+                out.push_str(&after[..after.len() - value.len()]);
+                let end = value
+                    .find(|c: char| c == '&' || c == ';' || c == ',' || c == ')' || c == '"' || c == '\'' || c.is_whitespace())
+                    .unwrap_or(value.len());
+                out.push_str(MASK);
+                rest = &value[end..];
+            } else {
+                rest = after;
+            }
+        }
+        out.push_str(rest);
+        out
+    }
+
+    pub fn truncate(&self, text: String) -> String {
+        if self.max_body_length == 0 {
+            return text;
+        }
+        match text.char_indices().nth(self.max_body_length) {
+            Some((index, _)) => format!("{}…({} bytes omitted)", &text[..index], text.len() - index),
+            None => text,
+        }
+    }
+}
+
+fn mask_bearer(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(index) = rest.find("Bearer ") {
+        let (before, after) = rest.split_at(index + "Bearer ".len());
+        out.push_str(before);
+        // 令牌只包含 base64url / JWT 字符
+        let end = after
+            .find(|c: char| !(c.is_ascii_alphanumeric() || "-._~+/=".contains(c)))
+            .unwrap_or(after.len());
+        out.push_str(MASK);
+        rest = &after[end..];
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn masks_json_fields_recursively() {
+        let redaction = LogRedaction::default();
+        let value = json!({
+            "username": "admin",
+            "password": "123456",
+            "data": { "token": "abc", "refresh_token": "def", "snapshot": 1 },
+            "items": [{ "sn": "xyz" }],
+        });
+        assert_eq!(
+            redaction.value(&value),
+            json!({
+                "username": "admin",
+                "password": "***",
+                "data": { "token": "***", "refresh_token": "***", "snapshot": 1 },
+                "items": [{ "sn": "***" }],
+            })
+        );
+    }
+
+    #[test]
+    fn masks_urls_forms_and_bearer_tokens() {
+        let redaction = LogRedaction::default();
+        assert_eq!(
+            redaction.text("http://host/api?gid=1&ts=2&sn=abc+def=&x=1"),
+            "http://host/api?gid=1&ts=2&sn=***&x=1"
+        );
+        assert_eq!(redaction.text("username=admin&password=p%40ss"), "username=admin&password=***");
+        assert_eq!(redaction.text("error (Authorization: Bearer eyJ.x.y)"), "error (Authorization: Bearer ***)");
+        assert_eq!(redaction.text("password = hunter2 ok"), "password = *** ok");
+
+        let headers = BTreeMap::from([
+            ("Authorization".to_string(), "Bearer abc".to_string()),
+            ("X-Access-Token".to_string(), "abc".to_string()),
+            ("Accept".to_string(), "application/json".to_string()),
+        ]);
+        let masked = redaction.pairs(&headers);
+        assert_eq!(masked["Authorization"], "***");
+        assert_eq!(masked["X-Access-Token"], "***");
+        assert_eq!(masked["Accept"], "application/json");
+    }
+
+    #[test]
+    fn masks_colon_separated_values() {
+        let redaction = LogRedaction::default();
+        assert_eq!(redaction.text("Connecting with token: eyJ.abc-def"), "Connecting with token: ***");
+        assert_eq!(redaction.text("refresh_token : r1, user: admin"), "refresh_token : ***, user: admin");
+        assert_eq!(redaction.text("WebSocket URL: ws://host:8183/ws"), "WebSocket URL: ws://host:8183/ws");
+        assert_eq!(redaction.text("at 12:30:45 sn:abc&x=1"), "at 12:30:45 sn:***&x=1");
+    }
+
+    #[test]
+    fn truncates_long_bodies() {
+        let redaction = LogRedaction {
+            max_body_length: 4,
+            ..Default::default()
+        };
+        assert_eq!(redaction.text("abcdefgh"), "abcd…(4 bytes omitted)");
+        assert_eq!(redaction.text("abc"), "abc");
+    }
+}
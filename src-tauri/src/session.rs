@@ -0,0 +1,253 @@
+use log::{error, info, warn};
+use reqwest::header::AUTHORIZATION;
+use reqwest::{StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::RwLock;
+use tauri::State;
+
+use crate::commands::{SResult, Seccess};
+use crate::error::AppError;
+use crate::events::{self, EventSink};
+use crate::http::HttpState;
+use crate::signing::{self, SigningKeys};
+
+const SESSION_FILE: &str = "session.enc";
+const REFRESH_PATH: &str = "ums/user/refresh-token";
+pub const EVENT_EXPIRED: &str = "session://expired";
+
+// 登录后得到的访问令牌和刷新令牌
+#[derive(Clone, Deserialize, Serialize)]
+pub struct SessionTokens {
+    pub token: String,
+    pub refresh_token: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct SessionExpired {
+    pub reason: String,
+}
+
+// 前端只能看到是否已登录，看不到令牌本身
+#[derive(Serialize)]
+pub struct SessionStatus {
+    pub authenticated: bool,
+    pub can_refresh: bool,
+}
+
+// 兼容两种响应格式：{ token, refresh_token } 或 { data: { token, refresh_token } }
+pub fn extract_tokens(body: &Value) -> Option<SessionTokens> {
+    let find = |key: &str| {
+        body.get(key)
+            .or_else(|| body.get("data").and_then(|data| data.get(key)))
+            .and_then(Value::as_str)
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+    };
+    Some(SessionTokens {
+        token: find("token")?,
+        refresh_token: find("refresh_token"),
+    })
+}
+
+// 令牌只保存在 Rust 端，返回给前端的数据中去掉令牌字段
+pub fn strip_tokens(body: &mut Value) {
+    for key in ["token", "refresh_token"] {
+        if let Some(object) = body.as_object_mut() {
+            object.remove(key);
+        }
+        if let Some(data) = body.get_mut("data").and_then(Value::as_object_mut) {
+            data.remove(key);
+        }
+    }
+}
+
+// 令牌只发送给后端所在的来源：协议、主机和端口都必须一致，ws/wss 分别视同 http/https
+pub fn same_origin(url: &str, base_url: &str) -> bool {
+    fn origin(url: &str) -> Option<(&'static str, String, u16)> {
+        let url = Url::parse(url).ok()?;
+        let scheme = match url.scheme() {
+            "http" | "ws" => "http",
+            "https" | "wss" => "https",
+            _ => return None,
+        };
+        Some((scheme, url.host_str()?.to_ascii_lowercase(), url.port_or_known_default()?))
+    }
+    match (origin(url), origin(base_url)) {
+        (Some(url), Some(base)) => url == base,
+        _ => false,
+    }
+}
+
+// 会话令牌保存在 Rust 端（加密写入 session.enc），刷新时同一时间只发起一次请求
+pub struct SessionState {
+    path: Option<PathBuf>,
+    tokens: RwLock<Option<SessionTokens>>,
+    refresh_lock: tokio::sync::Mutex<()>,
+}
+
+impl SessionState {
+    pub fn load(data_dir: Option<PathBuf>) -> Self {
+        let path = data_dir.map(|dir| dir.join(SESSION_FILE));
+        let tokens = path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|text| match signing::open(&text) {
+                Ok(plain) => serde_json::from_slice::<SessionTokens>(&plain).ok(),
+                Err(e) => {
+                    error!("Ignoring session file: {}", e);
+                    None
+                }
+            });
+        Self {
+            path,
+            tokens: RwLock::new(tokens),
+            refresh_lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    pub fn tokens(&self) -> Option<SessionTokens> {
+        self.tokens.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub fn access_token(&self) -> Option<String> {
+        self.tokens().map(|tokens| tokens.token)
+    }
+
+    pub fn set(&self, tokens: Option<SessionTokens>) {
+        if let Some(path) = &self.path {
+            let saved = match &tokens {
+                Some(tokens) => serde_json::to_vec(tokens)
+                    .map_err(|e| AppError::Validation(e.to_string()))
+                    .and_then(|plain| signing::seal(&plain))
+                    .and_then(|text| {
+                        if let Some(dir) = path.parent() {
+                            fs::create_dir_all(dir).map_err(|e| AppError::Validation(e.to_string()))?;
+                        }
+                        fs::write(path, text).map_err(|e| AppError::Validation(e.to_string()))
+                    }),
+                None => match fs::remove_file(path) {
+                    Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(AppError::Validation(e.to_string())),
+                    _ => Ok(()),
+                },
+            };
+            if let Err(e) = saved {
+                error!("Failed to persist session: {}", e);
+            }
+        }
+        *self.tokens.write().unwrap_or_else(|e| e.into_inner()) = tokens;
+    }
+
+    // 请求使用的令牌收到 401 后调用：若其他请求已经刷新过，直接返回新令牌
+    pub async fn refresh(
+        &self,
+        events: &dyn EventSink,
+        http: &HttpState,
+        keys: &SigningKeys,
+        stale_token: &str,
+    ) -> Result<String, AppError> {
+        let _guard = self.refresh_lock.lock().await;
+        let tokens = self
+            .tokens()
+            .ok_or_else(|| AppError::Auth("Not logged in".to_string()))?;
+        if tokens.token != stale_token {
+            return Ok(tokens.token);
+        }
+        let result = match &tokens.refresh_token {
+            Some(refresh_token) => request_refresh(http, keys, &tokens.token, refresh_token).await,
+            None => Err(AppError::Auth("No refresh token".to_string())),
+        };
+        match result {
+            Ok(mut refreshed) => {
+                info!("Session token refreshed");
+                // 后端未返回新的刷新令牌时沿用旧的
+                if refreshed.refresh_token.is_none() {
+                    refreshed.refresh_token = tokens.refresh_token;
+                }
+                let token = refreshed.token.clone();
+                self.set(Some(refreshed));
+                Ok(token)
+            }
+            // 网络问题不代表会话失效，保留令牌等待下次重试
+            Err(AppError::Auth(reason)) => {
+                warn!("Session expired: {}", reason);
+                self.set(None);
+                events::emit(events, EVENT_EXPIRED, SessionExpired { reason: reason.clone() });
+                Err(AppError::Auth(reason))
+            }
+            Err(err) => Err(err),
+        }
+    }
+}
+
+async fn request_refresh(
+    http: &HttpState,
+    keys: &SigningKeys,
+    token: &str,
+    refresh_token: &str,
+) -> Result<SessionTokens, AppError> {
+    let base_url = http.settings().base_url;
+    let url = Url::parse(&base_url)
+        .and_then(|base| base.join(REFRESH_PATH))
+        .map_err(|e| AppError::Validation(format!("Invalid base url {}: {}", base_url, e)))?;
+    let mut params = BTreeMap::from([("refresh_token".to_string(), refresh_token.to_string())]);
+    keys.sign_params(&mut params, signing::timestamp());
+    let form: HashMap<String, String> = params.into_iter().collect();
+
+    let request = http
+        .client()
+        .post(url)
+        .header(AUTHORIZATION, format!("Bearer {}", token))
+        .form(&form);
+    let response = http.with_read_timeout(request.send()).await?;
+    let status = response.status();
+    let body = http.with_read_timeout(response.text()).await?;
+    if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN {
+        return Err(AppError::Auth(format!("Refresh rejected: {}", body)));
+    }
+    if !status.is_success() {
+        return Err(AppError::Network(format!("Status code: {} - {}", status, body)));
+    }
+    let value: Value = serde_json::from_str(&body)
+        .map_err(|_| AppError::Network("Failed to parse refresh response".to_string()))?;
+    extract_tokens(&value).ok_or_else(|| AppError::Auth("Refresh response has no token".to_string()))
+}
+
+#[tauri::command]
+pub fn get_session_status(session: State<'_, SessionState>) -> SResult<SessionStatus> {
+    let tokens = session.tokens();
+    Ok(Seccess {
+        error: None,
+        message: None,
+        data: Some(SessionStatus {
+            authenticated: tokens.is_some(),
+            can_refresh: matches!(tokens, Some(SessionTokens { refresh_token: Some(_), .. })),
+        }),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_origin_compares_scheme_host_and_port() {
+        let base = "http://8.137.92.236:8988/api/";
+        assert!(same_origin("http://8.137.92.236:8988/api/user/info", base));
+        assert!(!same_origin("https://8.137.92.236:8988/api/", base));
+        assert!(!same_origin("http://8.137.92.236:9000/api/", base));
+        assert!(!same_origin("http://8.137.92.237:8988/api/", base));
+        assert!(!same_origin("not a url", base));
+    }
+
+    #[test]
+    fn same_origin_maps_websocket_schemes_and_default_ports() {
+        assert!(same_origin("ws://Example.com/ws", "http://example.com:80/api/"));
+        assert!(same_origin("wss://example.com/ws", "https://example.com/api/"));
+        assert!(!same_origin("ws://example.com/ws", "https://example.com/api/"));
+        assert!(!same_origin("wss://example.com:8443/ws", "https://example.com/api/"));
+    }
+}
@@ -0,0 +1,325 @@
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::Engine;
+use cbc::cipher::{block_padding::Pkcs7, BlockEncryptMut, KeyIvInit};
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::RwLock;
+use tauri::State;
+
+use crate::commands::{respond, SResult};
+use crate::error::AppError;
+
+type Aes128CbcEnc = cbc::Encryptor<aes::Aes128>;
+
+const CONFIG_FILE: &str = "signing.enc";
+// 加密配置文件所用的密钥，发布构建时通过 EMAIL_MASTER_CONFIG_SECRET 环境变量提供；
+// 未提供时照常编译，只是无法读写 signing.enc 和 session.enc
+#[cfg(not(test))]
+const CONFIG_SECRET: Option<&str> = option_env!("EMAIL_MASTER_CONFIG_SECRET");
+// 测试使用固定的密钥，不依赖构建环境
+#[cfg(test)]
+const CONFIG_SECRET: Option<&str> = Some("email-master-test-secret");
+
+// 请求签名所用的 AES-128-CBC 密钥和 IV
+#[derive(Clone, Deserialize, Serialize)]
+pub struct SigningKeys {
+    pub key: String,
+    pub iv: String,
+}
+
+// 测试用的非生产密钥
+#[cfg(test)]
+impl Default for SigningKeys {
+    fn default() -> Self {
+        Self {
+            key: "0123456789abcdef".to_string(),
+            iv: "fedcba9876543210".to_string(),
+        }
+    }
+}
+
+impl SigningKeys {
+    fn validate(&self) -> Result<(), AppError> {
+        if self.key.len() != 16 || self.iv.len() != 16 {
+            return Err(AppError::Validation("Signing key and iv must be 16 bytes".to_string()));
+        }
+        Ok(())
+    }
+
+    // 参数按 key 排序后拼成 `k=v&k=v`，AES-CBC 加密，再转成 websafe base64
+    pub fn sign(&self, params: &BTreeMap<String, String>) -> String {
+        let plain = params
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect::<Vec<_>>()
+            .join("&");
+        let encrypted = Aes128CbcEnc::new(self.key.as_bytes().into(), self.iv.as_bytes().into())
+            .encrypt_padded_vec_mut::<Pkcs7>(plain.as_bytes());
+        base64::engine::general_purpose::STANDARD
+            .encode(encrypted)
+            .replace('+', "-")
+            .replace('/', "_")
+    }
+
+    // 为一组参数追加 ts 和 sn
+    pub fn sign_params(&self, params: &mut BTreeMap<String, String>, ts: i64) {
+        params.insert("ts".to_string(), ts.to_string());
+        let sn = self.sign(params);
+        params.insert("sn".to_string(), sn);
+    }
+
+    // 为 JSON 对象的顶层字段签名（HTTP JSON 请求体和 WebSocket 消息共用）
+    pub fn sign_object(&self, object: &mut Map<String, Value>, ts: i64) {
+        let mut params: BTreeMap<String, String> = object
+            .iter()
+            .map(|(key, value)| (key.clone(), param_value(value.clone())))
+            .collect();
+        self.sign_params(&mut params, ts);
+        object.insert("ts".to_string(), Value::String(params["ts"].clone()));
+        object.insert("sn".to_string(), Value::String(params["sn"].clone()));
+    }
+}
+
+// 参数值统一转成字符串：数字、布尔、null 直接转换（与旧版前端的模板字符串一致），对象和数组序列化为 JSON
+pub fn param_value(value: Value) -> String {
+    match value {
+        Value::String(value) => value,
+        value => value.to_string(),
+    }
+}
+
+pub fn timestamp() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+fn config_cipher() -> Result<Aes256Gcm, AppError> {
+    cipher_for(CONFIG_SECRET)
+}
+
+fn cipher_for(secret: Option<&str>) -> Result<Aes256Gcm, AppError> {
+    let secret = secret.ok_or_else(|| {
+        AppError::Validation("EMAIL_MASTER_CONFIG_SECRET was not set at build time, config files are unavailable".to_string())
+    })?;
+    Ok(Aes256Gcm::new(&Sha256::digest(secret.as_bytes())))
+}
+
+// 加密配置文件格式：base64(12 字节 nonce + AES-256-GCM 密文)，会话令牌文件也使用同样的格式
+pub fn seal(plain: &[u8]) -> Result<String, AppError> {
+    let nonce: [u8; 12] = rand::random();
+    let ciphertext = config_cipher()?
+        .encrypt(Nonce::from_slice(&nonce), plain)
+        .map_err(|_| AppError::Validation("Failed to encrypt config".to_string()))?;
+    let mut bytes = nonce.to_vec();
+    bytes.extend(ciphertext);
+    Ok(base64::engine::general_purpose::STANDARD.encode(bytes))
+}
+
+pub fn open(text: &str) -> Result<Vec<u8>, String> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(text.trim())
+        .map_err(|_| "not base64".to_string())?;
+    if bytes.len() < 12 {
+        return Err("too short".to_string());
+    }
+    let (nonce, ciphertext) = bytes.split_at(12);
+    config_cipher()
+        .map_err(|e| e.to_string())?
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "decryption failed".to_string())
+}
+
+fn decrypt_config(text: &str) -> Result<SigningKeys, AppError> {
+    let invalid = |detail: &str| AppError::Validation(format!("Invalid {}: {}", CONFIG_FILE, detail));
+    let plain = open(text).map_err(|e| invalid(&e))?;
+    let keys: SigningKeys = serde_json::from_slice(&plain).map_err(|e| invalid(&e.to_string()))?;
+    keys.validate()?;
+    Ok(keys)
+}
+
+fn encrypt_config(keys: &SigningKeys) -> Result<String, AppError> {
+    let plain = serde_json::to_vec(keys).map_err(|e| AppError::Validation(e.to_string()))?;
+    seal(&plain)
+}
+
+// 签名密钥只保存在 Rust 端，前端无法读取；未配置 signing.enc 时无法签名
+pub struct SigningState {
+    path: Option<PathBuf>,
+    keys: RwLock<Option<SigningKeys>>,
+}
+
+impl SigningState {
+    pub fn load(config_dir: Option<PathBuf>) -> Self {
+        let path = config_dir.map(|dir| dir.join(CONFIG_FILE));
+        let keys = path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|text| match decrypt_config(&text) {
+                Ok(keys) => Some(keys),
+                Err(e) => {
+                    error!("Ignoring signing config: {}", e);
+                    None
+                }
+            });
+        if keys.is_none() {
+            info!("No signing keys configured, signed requests will be rejected");
+        }
+        Self {
+            path,
+            keys: RwLock::new(keys),
+        }
+    }
+
+    pub fn keys(&self) -> Result<SigningKeys, AppError> {
+        self.keys
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+            .ok_or_else(|| AppError::Validation(format!("Signing keys are not configured ({} is missing)", CONFIG_FILE)))
+    }
+
+    pub fn update(&self, keys: SigningKeys) -> Result<(), AppError> {
+        keys.validate()?;
+        if let Some(path) = &self.path {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)
+                    .map_err(|e| AppError::Validation(format!("Failed to create {}: {}", dir.display(), e)))?;
+            }
+            fs::write(path, encrypt_config(&keys)?)
+                .map_err(|e| AppError::Validation(format!("Failed to write {}: {}", path.display(), e)))?;
+        }
+        *self.keys.write().unwrap_or_else(|e| e.into_inner()) = Some(keys);
+        info!("Signing keys updated");
+        Ok(())
+    }
+}
+
+// 更新签名密钥，写入加密的配置文件；不提供读取密钥的命令
+#[tauri::command]
+pub fn update_signing_keys(signing: State<'_, SigningState>, key: String, iv: String) -> SResult<()> {
+    respond(signing.update(SigningKeys { key, iv }), Some("update signing keys success"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    // 旧版前端写死的密钥，已经公开在源码和 git 历史中，后端必须轮换；这里只用来核对旧版生成的向量
+    fn legacy_keys() -> SigningKeys {
+        SigningKeys {
+            key: "YEY3NKU5CgVoqxDu".to_string(),
+            iv: "YEY3NKU5CgVoqxDu".to_string(),
+        }
+    }
+
+    // 以下向量由旧版 src/api.ts 的 generateSignature 生成
+    #[test]
+    fn matches_typescript_vectors() {
+        let keys = legacy_keys();
+        let vectors = [
+            (
+                params(&[("username", "admin"), ("password", "123456"), ("ts", "1700000000")]),
+                "pKTz7ku5KTMQeaZGeya963zX8NGZQAVMoQrRQf_e6aCNz242Gt4Pb_oPYCwnqUIP",
+            ),
+            (
+                params(&[("ts", "1700000000"), ("refresh_token", "abc.def+ghi")]),
+                "myim6S1IXOzBiY9xEJrxla6zf2UEKGvnV1warkKA7NosKd4DX7abc_ZO6C0v7Isf",
+            ),
+            (params(&[("gid", "12"), ("ts", "1712345678")]), "JJYEtbExAsgqQ3VSpQOjSK643diXQOzX7rao6_irbcA="),
+            (params(&[("ts", "1712345678")]), "bvyjYh2alrbAFkS6YgFgJw=="),
+            (
+                params(&[("name", "邮箱分组"), ("ts", "1712345678")]),
+                "g0t3nWRtEQccEKHT3l7kHi9fHNuHkj9spORYiOUzo-Y=",
+            ),
+        ];
+        for (params, expected) in vectors {
+            assert_eq!(keys.sign(&params), expected);
+        }
+    }
+
+    // 同样的算法换成测试密钥，向量由 Node 的 aes-128-cbc 生成，不依赖生产密钥
+    #[test]
+    fn matches_vectors_for_a_test_key() {
+        let keys = SigningKeys::default();
+        let vectors = [
+            (
+                params(&[("username", "admin"), ("password", "123456"), ("ts", "1700000000")]),
+                "kp9GkooSdRrhJtVqAAEg0KSkk2wsfTcps1f8yhcdIa0sIqdhONcVc0P8YWKYIVvc",
+            ),
+            (params(&[("gid", "12"), ("ts", "1712345678")]), "LH3vs6ryxcSESGuuMwFiWvUsAZ3KAKbLfEx1YS5VB88="),
+            (
+                params(&[("name", "邮箱分组"), ("ts", "1712345678")]),
+                "ikCmjxuMZEX8xk_X4OopXnwojsrEcCK30RW3TR9CYl4=",
+            ),
+        ];
+        for (params, expected) in vectors {
+            assert_eq!(keys.sign(&params), expected);
+        }
+    }
+
+    #[test]
+    fn sign_params_appends_ts_and_sn() {
+        let keys = SigningKeys::default();
+        let mut signed = params(&[("username", "admin"), ("password", "123456")]);
+        keys.sign_params(&mut signed, 1700000000);
+        assert_eq!(signed["ts"], "1700000000");
+        assert_eq!(signed["sn"], "kp9GkooSdRrhJtVqAAEg0KSkk2wsfTcps1f8yhcdIa0sIqdhONcVc0P8YWKYIVvc");
+    }
+
+    #[test]
+    fn sign_object_stringifies_numbers() {
+        let keys = SigningKeys::default();
+        let mut object = serde_json::json!({ "gid": 12 }).as_object().unwrap().clone();
+        keys.sign_object(&mut object, 1712345678);
+        assert_eq!(object["gid"], 12);
+        assert_eq!(object["ts"], "1712345678");
+        assert_eq!(object["sn"], "LH3vs6ryxcSESGuuMwFiWvUsAZ3KAKbLfEx1YS5VB88=");
+    }
+
+    #[test]
+    fn sign_object_stringifies_null_like_typescript() {
+        let keys = SigningKeys::default();
+        let mut object = serde_json::json!({ "gid": 12, "remark": null }).as_object().unwrap().clone();
+        keys.sign_object(&mut object, 1712345678);
+        assert_eq!(param_value(Value::Null), "null");
+        assert_eq!(object["sn"], "GdYN6vLyHW1ynwfG8VhvpnqVxtdiqAbZ559cTxs8xghizAFTmOh6a13BsS1Pd9ju");
+    }
+
+    #[test]
+    fn state_without_config_cannot_sign() {
+        let dir = std::env::temp_dir().join(format!("signing-{}", uuid::Uuid::now_v7()));
+        let state = SigningState::load(Some(dir.clone()));
+        assert_eq!(state.keys().err().map(|e| e.code()), Some("VALIDATION"));
+
+        state.update(SigningKeys::default()).unwrap();
+        assert_eq!(SigningState::load(Some(dir.clone())).keys().unwrap().key, "0123456789abcdef");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn config_round_trips() {
+        let keys = SigningKeys {
+            key: "0123456789abcdef".to_string(),
+            iv: "fedcba9876543210".to_string(),
+        };
+        let decrypted = decrypt_config(&encrypt_config(&keys).unwrap()).unwrap();
+        assert_eq!(decrypted.key, keys.key);
+        assert_eq!(decrypted.iv, keys.iv);
+        assert!(decrypt_config("not a config").is_err());
+    }
+
+    #[test]
+    fn missing_config_secret_is_a_validation_error() {
+        assert_eq!(cipher_for(None).err().map(|e| e.code()), Some("VALIDATION"));
+        assert!(cipher_for(Some("another-secret")).is_ok());
+    }
+}
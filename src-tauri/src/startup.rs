@@ -0,0 +1,27 @@
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::error::AppError;
+
+// 启动阶段（连接数据库、执行迁移）的结果，失败时由前端展示错误页
+#[derive(Default)]
+pub struct StartupState {
+    pub error: Option<StartupError>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct StartupError {
+    pub stage: &'static str,
+    pub error: AppError,
+}
+
+#[tauri::command]
+pub fn get_startup_error(state: tauri::State<'_, StartupState>) -> Option<StartupError> {
+    state.error.clone()
+}
+
+// 修复数据库后重启应用，重新执行启动流程
+#[tauri::command]
+pub fn restart_app(app: AppHandle) {
+    app.restart();
+}
@@ -0,0 +1,401 @@
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::Engine;
+use log::{info, warn};
+use serde::Serialize;
+use service::sea_orm::DatabaseConnection as DbConn;
+use service::{Credential, MutationsService, NewCredential, QueriesService, SelectCredentials, VaultParams};
+use tauri::State;
+use tokio::sync::RwLock;
+
+use crate::commands::{respond, SResult};
+use crate::error::AppError;
+use crate::AppState;
+
+// 用口令解密这段固定内容来判断口令是否正确
+const VERIFIER_PLAIN: &[u8] = b"email-master/vault";
+const VERIFIER_AAD: &[u8] = b"vault";
+const MIN_PASSPHRASE_LEN: usize = 8;
+
+type VaultKey = [u8; 32];
+
+#[derive(Serialize)]
+pub struct VaultStatus {
+    pub initialized: bool,
+    pub unlocked: bool,
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+}
+
+fn base64_decode(text: &str) -> Result<Vec<u8>, AppError> {
+    base64::engine::general_purpose::STANDARD
+        .decode(text)
+        .map_err(|_| AppError::Validation("Corrupted vault data".to_string()))
+}
+
+// Argon2id 派生 256 位密钥，参数随 vault 一起保存，以后调整默认参数不影响已有数据
+fn derive_key(passphrase: &str, params: &VaultParams) -> Result<VaultKey, AppError> {
+    let salt = base64_decode(&params.salt)?;
+    let argon_params = Params::new(params.memory_cost, params.time_cost, params.parallelism, Some(32))
+        .map_err(|e| AppError::Validation(format!("Invalid vault parameters: {}", e)))?;
+    let mut key = [0u8; 32];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, argon_params)
+        .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+        .map_err(|e| AppError::Validation(format!("Failed to derive vault key: {}", e)))?;
+    Ok(key)
+}
+
+// 派生密钥比较耗时，放到阻塞线程中执行
+async fn derive_key_blocking(passphrase: String, params: VaultParams) -> Result<VaultKey, AppError> {
+    tokio::task::spawn_blocking(move || derive_key(&passphrase, &params))
+        .await
+        .map_err(|e| AppError::Validation(format!("Failed to derive vault key: {}", e)))?
+}
+
+// 密文格式：base64(12 字节 nonce + AES-256-GCM 密文)，kind 和 account 作为附加数据防止密文被挪到其他记录
+fn encrypt(key: &VaultKey, aad: &[u8], plain: &[u8]) -> Result<String, AppError> {
+    let nonce: [u8; 12] = rand::random();
+    let ciphertext = Aes256Gcm::new(key.into())
+        .encrypt(Nonce::from_slice(&nonce), Payload { msg: plain, aad })
+        .map_err(|_| AppError::Validation("Failed to encrypt secret".to_string()))?;
+    let mut bytes = nonce.to_vec();
+    bytes.extend(ciphertext);
+    Ok(base64_encode(&bytes))
+}
+
+fn decrypt(key: &VaultKey, aad: &[u8], text: &str) -> Result<Vec<u8>, AppError> {
+    let bytes = base64_decode(text)?;
+    if bytes.len() < 12 {
+        return Err(AppError::Validation("Corrupted vault data".to_string()));
+    }
+    let (nonce, ciphertext) = bytes.split_at(12);
+    Aes256Gcm::new(key.into())
+        .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad })
+        .map_err(|_| AppError::Auth("Failed to decrypt secret".to_string()))
+}
+
+fn credential_aad(kind: &str, account: &str) -> Vec<u8> {
+    format!("{}\n{}", kind, account).into_bytes()
+}
+
+fn validate_passphrase(passphrase: &str) -> Result<(), AppError> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(AppError::Validation(format!(
+            "Passphrase must be at least {} characters",
+            MIN_PASSPHRASE_LEN
+        )));
+    }
+    Ok(())
+}
+
+// 新口令使用新的随机盐和当前默认的 Argon2 参数
+async fn new_vault(passphrase: &str) -> Result<(VaultParams, VaultKey), AppError> {
+    validate_passphrase(passphrase)?;
+    let salt: [u8; 16] = rand::random();
+    let mut params = VaultParams {
+        salt: base64_encode(&salt),
+        memory_cost: Params::DEFAULT_M_COST,
+        time_cost: Params::DEFAULT_T_COST,
+        parallelism: Params::DEFAULT_P_COST,
+        verifier: String::new(),
+    };
+    let key = derive_key_blocking(passphrase.to_string(), params.clone()).await?;
+    params.verifier = encrypt(&key, VERIFIER_AAD, VERIFIER_PLAIN)?;
+    Ok((params, key))
+}
+
+async fn open_vault(passphrase: &str, params: VaultParams) -> Result<VaultKey, AppError> {
+    let verifier = params.verifier.clone();
+    let key = derive_key_blocking(passphrase.to_string(), params).await?;
+    match decrypt(&key, VERIFIER_AAD, &verifier) {
+        Ok(plain) if plain == VERIFIER_PLAIN => Ok(key),
+        _ => Err(AppError::Auth("Invalid vault passphrase".to_string())),
+    }
+}
+
+// 解锁后的密钥只保存在内存中，数据库里只有密文
+#[derive(Default)]
+pub struct VaultState {
+    key: RwLock<Option<VaultKey>>,
+}
+
+impl VaultState {
+    pub async fn status(&self, db: &DbConn) -> Result<VaultStatus, AppError> {
+        Ok(VaultStatus {
+            initialized: QueriesService::get_vault(db).await?.is_some(),
+            unlocked: self.key.read().await.is_some(),
+        })
+    }
+
+    // 第一次解锁时用该口令建立 vault
+    pub async fn unlock(&self, db: &DbConn, passphrase: &str) -> Result<(), AppError> {
+        let key = match QueriesService::get_vault(db).await? {
+            Some(params) => open_vault(passphrase, params).await?,
+            None => {
+                let (params, key) = new_vault(passphrase).await?;
+                MutationsService::create_vault(db, params).await?;
+                info!("Credential vault created");
+                key
+            }
+        };
+        *self.key.write().await = Some(key);
+        info!("Credential vault unlocked");
+        Ok(())
+    }
+
+    pub async fn lock(&self) {
+        *self.key.write().await = None;
+        info!("Credential vault locked");
+    }
+
+    pub async fn store(&self, db: &DbConn, kind: String, account: String, secret: &str) -> Result<String, AppError> {
+        let guard = self.key.read().await;
+        let key = guard.as_ref().ok_or_else(locked)?;
+        let secret = encrypt(key, &credential_aad(&kind, &account), secret.as_bytes())?;
+        Ok(MutationsService::upsert_credential(db, NewCredential { kind, account, secret }).await?)
+    }
+
+    pub async fn read(&self, db: &DbConn, kind: String, account: String) -> Result<String, AppError> {
+        let guard = self.key.read().await;
+        let key = guard.as_ref().ok_or_else(locked)?;
+        let credential = QueriesService::get_credential(db, kind.clone(), account.clone())
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("credential {}/{} not found", kind, account)))?;
+        let plain = decrypt(key, &credential_aad(&kind, &account), &credential.secret)?;
+        String::from_utf8(plain).map_err(|_| AppError::Validation("Corrupted vault data".to_string()))
+    }
+
+    // 只列出账号，不解密；锁定时连账号列表也不返回
+    pub async fn list(&self, db: &DbConn) -> Result<Vec<SelectCredentials>, AppError> {
+        let guard = self.key.read().await;
+        guard.as_ref().ok_or_else(locked)?;
+        Ok(QueriesService::list_credentials(db).await?)
+    }
+
+    pub async fn delete(&self, db: &DbConn, kind: String, account: String) -> Result<u64, AppError> {
+        let guard = self.key.read().await;
+        guard.as_ref().ok_or_else(locked)?;
+        Ok(MutationsService::delete_credential(db, kind, account).await?)
+    }
+
+    // 校验旧口令后，用新口令派生的密钥重新加密全部凭据
+    pub async fn rotate(&self, db: &DbConn, current: &str, new: &str) -> Result<(), AppError> {
+        let mut guard = self.key.write().await;
+        let params = QueriesService::get_vault(db)
+            .await?
+            .ok_or_else(|| AppError::NotFound("vault not found".to_string()))?;
+        let old_key = open_vault(current, params).await?;
+        let (params, new_key) = new_vault(new).await?;
+        let mut credentials = Vec::new();
+        for credential in QueriesService::list_credential_secrets(db).await? {
+            let aad = credential_aad(&credential.kind, &credential.account);
+            let plain = decrypt(&old_key, &aad, &credential.secret)?;
+            credentials.push(Credential {
+                secret: encrypt(&new_key, &aad, &plain)?,
+                ..credential
+            });
+        }
+        let count = credentials.len();
+        MutationsService::rotate_vault(db, params, credentials).await?;
+        *guard = Some(new_key);
+        info!("Credential vault passphrase rotated ({} secrets re-encrypted)", count);
+        Ok(())
+    }
+
+    // 忘记口令时只能清空全部凭据
+    pub async fn wipe(&self, db: &DbConn) -> Result<u64, AppError> {
+        let mut guard = self.key.write().await;
+        let removed = MutationsService::wipe_credentials(db).await?;
+        *guard = None;
+        warn!("Credential vault wiped ({} secrets removed)", removed);
+        Ok(removed)
+    }
+}
+
+fn locked() -> AppError {
+    AppError::Auth("Vault is locked".to_string())
+}
+
+#[tauri::command]
+pub async fn get_vault_status(state: State<'_, AppState>, vault: State<'_, VaultState>) -> SResult<VaultStatus> {
+    respond(vault.status(state.db()?).await, None)
+}
+
+#[tauri::command]
+pub async fn unlock_vault(state: State<'_, AppState>, vault: State<'_, VaultState>, passphrase: String) -> SResult<()> {
+    respond(vault.unlock(state.db()?, &passphrase).await, Some("vault unlocked"))
+}
+
+#[tauri::command]
+pub async fn lock_vault(vault: State<'_, VaultState>) -> SResult<()> {
+    vault.lock().await;
+    respond(Ok(()), Some("vault locked"))
+}
+
+#[tauri::command]
+pub async fn store_secret(
+    state: State<'_, AppState>,
+    vault: State<'_, VaultState>,
+    kind: String,
+    account: String,
+    secret: String,
+) -> SResult<String> {
+    respond(
+        vault.store(state.db()?, kind, account, &secret).await,
+        Some("secret stored successfully"),
+    )
+}
+
+#[tauri::command]
+pub async fn read_secret(
+    state: State<'_, AppState>,
+    vault: State<'_, VaultState>,
+    kind: String,
+    account: String,
+) -> SResult<String> {
+    respond(vault.read(state.db()?, kind, account).await, None)
+}
+
+#[tauri::command]
+pub async fn list_secrets(state: State<'_, AppState>, vault: State<'_, VaultState>) -> SResult<Vec<SelectCredentials>> {
+    respond(vault.list(state.db()?).await, None)
+}
+
+#[tauri::command]
+pub async fn delete_secret(
+    state: State<'_, AppState>,
+    vault: State<'_, VaultState>,
+    kind: String,
+    account: String,
+) -> SResult<u64> {
+    respond(vault.delete(state.db()?, kind, account).await, None)
+}
+
+#[tauri::command]
+pub async fn rotate_vault_passphrase(
+    state: State<'_, AppState>,
+    vault: State<'_, VaultState>,
+    current_passphrase: String,
+    new_passphrase: String,
+) -> SResult<()> {
+    respond(
+        vault.rotate(state.db()?, &current_passphrase, &new_passphrase).await,
+        Some("vault passphrase rotated"),
+    )
+}
+
+#[tauri::command]
+pub async fn wipe_secrets(state: State<'_, AppState>, vault: State<'_, VaultState>) -> SResult<u64> {
+    respond(vault.wipe(state.db()?).await, Some("secrets wiped"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use migration::{Migrator, MigratorTrait};
+    use service::sea_orm::Database;
+
+    const PASSPHRASE: &str = "correct horse";
+
+    fn params() -> VaultParams {
+        VaultParams {
+            salt: base64_encode(b"0123456789abcdef"),
+            memory_cost: Params::MIN_M_COST.max(64),
+            time_cost: 1,
+            parallelism: 1,
+            verifier: String::new(),
+        }
+    }
+
+    #[test]
+    fn secrets_round_trip_and_bind_to_account() {
+        let key = derive_key("correct horse", &params()).unwrap();
+        let aad = credential_aad("mailbox", "a@example.com");
+        let sealed = encrypt(&key, &aad, b"hunter2").unwrap();
+        assert_eq!(decrypt(&key, &aad, &sealed).unwrap(), b"hunter2");
+        assert!(decrypt(&key, &credential_aad("mailbox", "b@example.com"), &sealed).is_err());
+
+        let other = derive_key("wrong horse", &params()).unwrap();
+        assert!(decrypt(&other, &aad, &sealed).is_err());
+    }
+
+    // 用低成本参数预先建好 vault，避免测试里跑默认的 Argon2 参数
+    async fn database() -> DbConn {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+        let mut params = params();
+        let key = derive_key(PASSPHRASE, &params).unwrap();
+        params.verifier = encrypt(&key, VERIFIER_AAD, VERIFIER_PLAIN).unwrap();
+        MutationsService::create_vault(&db, params).await.unwrap();
+        db
+    }
+
+    fn is_auth(res: Result<impl Sized, AppError>) -> bool {
+        matches!(res, Err(AppError::Auth(_)))
+    }
+
+    #[tokio::test]
+    async fn locked_vault_rejects_every_credential_operation() {
+        let db = database().await;
+        let vault = VaultState::default();
+        vault.unlock(&db, PASSPHRASE).await.unwrap();
+        vault.store(&db, "mailbox".into(), "a@example.com".into(), "hunter2").await.unwrap();
+        vault.lock().await;
+
+        assert!(!vault.status(&db).await.unwrap().unlocked);
+        assert!(is_auth(vault.list(&db).await));
+        assert!(is_auth(vault.delete(&db, "mailbox".into(), "a@example.com".into()).await));
+        assert!(is_auth(vault.read(&db, "mailbox".into(), "a@example.com".into()).await));
+        assert!(is_auth(vault.store(&db, "mailbox".into(), "b@example.com".into(), "x").await));
+        // 锁定时的删除请求没有生效
+        assert_eq!(QueriesService::list_credentials(&db).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn unlocked_vault_lists_reads_and_deletes() {
+        let db = database().await;
+        let vault = VaultState::default();
+        vault.unlock(&db, PASSPHRASE).await.unwrap();
+        vault.store(&db, "mailbox".into(), "a@example.com".into(), "hunter2").await.unwrap();
+
+        let listed = vault.list(&db).await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].account, "a@example.com");
+        assert_eq!(vault.read(&db, "mailbox".into(), "a@example.com".into()).await.unwrap(), "hunter2");
+        assert_eq!(vault.delete(&db, "mailbox".into(), "a@example.com".into()).await.unwrap(), 1);
+        assert!(vault.list(&db).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn wrong_passphrase_keeps_the_vault_locked() {
+        let db = database().await;
+        let vault = VaultState::default();
+
+        assert!(is_auth(vault.unlock(&db, "wrong horse").await));
+        assert!(!vault.status(&db).await.unwrap().unlocked);
+        assert!(is_auth(vault.list(&db).await));
+    }
+
+    #[tokio::test]
+    async fn moved_ciphertext_fails_to_decrypt() {
+        let db = database().await;
+        let vault = VaultState::default();
+        vault.unlock(&db, PASSPHRASE).await.unwrap();
+        vault.store(&db, "mailbox".into(), "a@example.com".into(), "hunter2").await.unwrap();
+        let sealed = QueriesService::get_credential(&db, "mailbox".into(), "a@example.com".into())
+            .await
+            .unwrap()
+            .unwrap()
+            .secret;
+
+        // 把密文原样挪到其他账号或其他类型下
+        for (kind, account) in [("mailbox", "b@example.com"), ("smtp", "a@example.com")] {
+            let moved = NewCredential { kind: kind.into(), account: account.into(), secret: sealed.clone() };
+            MutationsService::upsert_credential(&db, moved).await.unwrap();
+            assert!(is_auth(vault.read(&db, kind.into(), account.into()).await), "{}/{}", kind, account);
+        }
+    }
+}
@@ -0,0 +1,1187 @@
+use tauri::{AppHandle, Manager, State};
+use tokio::net::TcpStream;
+use tokio::sync::{oneshot, watch};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tokio_tungstenite::{
+    connect_async,
+    tungstenite::{
+        client::IntoClientRequest,
+        http::{header::AUTHORIZATION, HeaderMap, HeaderName, HeaderValue},
+        protocol::Message,
+    },
+    MaybeTlsStream, WebSocketStream,
+};
+use futures_util::stream::{SplitSink, SplitStream};
+use futures_util::{SinkExt, StreamExt};
+use log::{info, error};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use std::collections::{HashMap, VecDeque};
+
+pub mod protocol;
+#[cfg(test)]
+mod tests;
+
+use crate::error::AppError;
+use crate::events::{self, EventSink};
+use crate::http::HttpState;
+use crate::redact::LogRedaction;
+use crate::session::SessionState;
+use crate::signing::{self, SigningKeys, SigningState};
+use protocol::{ClientMessage, ProtocolError, ServerMessage};
+
+// ws:// 为明文 TcpStream，wss:// 为 TLS 包装后的流
+type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+type WsSink = SplitSink<WsStream, Message>;
+type WsSource = SplitStream<WsStream>;
+
+// 推送给前端的事件名
+pub const EVENT_MESSAGE: &str = "ws://message";
+pub const EVENT_CLOSED: &str = "ws://closed";
+pub const EVENT_ERROR: &str = "ws://error";
+pub const EVENT_STATUS: &str = "ws://status";
+pub const EVENT_OUTBOX: &str = "ws://outbox";
+
+// 关闭连接时等待后台任务退出的最长时间
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(3);
+// 心跳间隔下限，避免前端误传过小的值压垮服务端
+const MIN_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(1);
+// 断线期间每个会话最多缓存的待发消息数
+const DEFAULT_OUTBOX_CAPACITY: usize = 256;
+// websocket_request 默认的请求 id 字段与等待回复的超时时间
+const DEFAULT_REQUEST_ID_FIELD: &str = "request_id";
+const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+// WebSocket State Struct for managing the WebSocket connections, keyed by caller-chosen session id.
+#[derive(Default)]
+pub struct WebSocketState {
+    sessions: HashMap<String, ActiveConnection>,
+}
+
+struct ActiveConnection {
+    connection: Arc<Connection>,
+    supervisor: JoinHandle<()>,
+}
+
+// 单个连接的配置及运行时状态，由后台守护任务与各命令共享
+struct Connection {
+    id: String,
+    url: String,
+    headers: HeaderMap,
+    initial_message: String,
+    // 是否按邮件任务协议校验收发的消息
+    validate: bool,
+    heartbeat: HeartbeatConfig,
+    reconnect: ReconnectPolicy,
+    sink: Mutex<Option<WsSink>>,
+    // 锁顺序：先 outbox 后 sink，保证排队消息与新消息的发送顺序
+    outbox: Mutex<Outbox>,
+    status: Mutex<WsStatus>,
+    shutdown: watch::Sender<bool>,
+    bytes_sent: AtomicU64,
+    bytes_received: AtomicU64,
+    // 等待服务端回复的请求，键为请求 id
+    pending: Mutex<HashMap<String, PendingRequest>>,
+}
+
+struct PendingRequest {
+    id_field: String,
+    reply: oneshot::Sender<Value>,
+}
+
+// 心跳配置：payload 为可选的应用层心跳模板，`{ts}` 会被替换为当前秒级时间戳
+#[derive(Clone, Debug, Deserialize)]
+pub struct HeartbeatConfig {
+    #[serde(default = "default_heartbeat_interval")]
+    pub interval_ms: u64,
+    #[serde(default)]
+    pub payload: Option<String>,
+    // 连续多少个心跳周期未收到任何帧（包括 Pong）即判定连接失效
+    #[serde(default = "default_max_missed")]
+    pub max_missed: u32,
+}
+
+fn default_heartbeat_interval() -> u64 {
+    50_000
+}
+
+fn default_max_missed() -> u32 {
+    2
+}
+
+impl Default for HeartbeatConfig {
+    fn default() -> Self {
+        Self {
+            interval_ms: default_heartbeat_interval(),
+            payload: None,
+            max_missed: default_max_missed(),
+        }
+    }
+}
+
+// 断线重连策略：指数退避，封顶 max_delay_ms，并叠加 ±jitter 比例的随机抖动
+#[derive(Clone, Debug, Deserialize)]
+pub struct ReconnectPolicy {
+    #[serde(default = "default_reconnect_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_initial_delay")]
+    pub initial_delay_ms: u64,
+    #[serde(default = "default_max_delay")]
+    pub max_delay_ms: u64,
+    #[serde(default = "default_jitter")]
+    pub jitter: f64,
+    // None 表示无限重试
+    #[serde(default)]
+    pub max_attempts: Option<u32>,
+}
+
+fn default_reconnect_enabled() -> bool {
+    true
+}
+
+fn default_initial_delay() -> u64 {
+    1_000
+}
+
+fn default_max_delay() -> u64 {
+    30_000
+}
+
+fn default_jitter() -> f64 {
+    0.2
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: default_reconnect_enabled(),
+            initial_delay_ms: default_initial_delay(),
+            max_delay_ms: default_max_delay(),
+            jitter: default_jitter(),
+            max_attempts: None,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    // 第 attempt 次重连前的等待时间（attempt 从 1 开始）
+    fn delay(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(16);
+        let base = self.initial_delay_ms.saturating_mul(1u64 << exponent).min(self.max_delay_ms) as f64;
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        let factor = 1.0 - jitter + rand::random::<f64>() * 2.0 * jitter;
+        Duration::from_millis((base * factor).min(self.max_delay_ms as f64) as u64)
+    }
+}
+
+// 断线期间缓存的待发消息
+struct QueuedMessage {
+    text: String,
+    expires_at: Option<Instant>,
+}
+
+// 有界发件箱：已满时丢弃最旧的消息，重连后按入队顺序补发
+struct Outbox {
+    queue: VecDeque<QueuedMessage>,
+    capacity: usize,
+    dropped: u64,
+    expired: u64,
+}
+
+impl Outbox {
+    fn new(capacity: usize) -> Self {
+        Self {
+            queue: VecDeque::new(),
+            capacity: capacity.max(1),
+            dropped: 0,
+            expired: 0,
+        }
+    }
+
+    // 入队，返回是否因溢出丢弃了最旧的消息
+    fn push(&mut self, message: QueuedMessage) -> bool {
+        let overflow = self.queue.len() >= self.capacity;
+        if overflow {
+            self.queue.pop_front();
+            self.dropped += 1;
+        }
+        self.queue.push_back(message);
+        overflow
+    }
+
+    fn stats(&self) -> OutboxStats {
+        OutboxStats {
+            depth: self.queue.len(),
+            capacity: self.capacity,
+            dropped: self.dropped,
+            expired: self.expired,
+        }
+    }
+}
+
+// ws://outbox 事件负载
+#[derive(Clone, Debug, Serialize)]
+pub struct OutboxStats {
+    depth: usize,
+    capacity: usize,
+    dropped: u64,
+    expired: u64,
+}
+
+// send_websocket_message 的投递结果
+enum Delivery {
+    Sent,
+    Queued,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConnectionStatus {
+    Idle,
+    Connecting,
+    Connected,
+    Reconnecting,
+    Closed,
+    Failed,
+}
+
+// ws://status 事件负载，同时作为 get_websocket_status 的返回值
+#[derive(Clone, Debug, Serialize)]
+pub struct WsStatus {
+    status: ConnectionStatus,
+    url: Option<String>,
+    attempt: u32,
+    connected_at: Option<String>,
+    #[serde(skip)]
+    connected_since: Option<Instant>,
+    next_retry_ms: Option<u64>,
+    last_error: Option<String>,
+}
+
+impl Default for WsStatus {
+    fn default() -> Self {
+        Self {
+            status: ConnectionStatus::Idle,
+            url: None,
+            attempt: 0,
+            connected_at: None,
+            connected_since: None,
+            next_retry_ms: None,
+            last_error: None,
+        }
+    }
+}
+
+// list_websocket_sessions 返回的单个会话概况
+#[derive(Clone, Debug, Serialize)]
+pub struct WsSessionInfo {
+    session_id: String,
+    url: String,
+    status: ConnectionStatus,
+    uptime_ms: Option<u64>,
+    bytes_sent: u64,
+    bytes_received: u64,
+    outbox: OutboxStats,
+    last_error: Option<String>,
+}
+
+// 所有推送给前端的事件都带上会话 id，便于区分来源
+#[derive(Clone, Serialize)]
+struct SessionEvent<T: Serialize + Clone> {
+    session_id: String,
+    #[serde(flatten)]
+    payload: T,
+}
+
+// ws://message 事件负载
+#[derive(Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum WsFrame {
+    Text {
+        text: String,
+        data: Option<Value>,
+        message: Option<ServerMessage>,
+    },
+    Binary { data: Vec<u8> },
+}
+
+// ws://closed 事件负载
+#[derive(Clone, Serialize)]
+pub struct WsClosed {
+    code: Option<u16>,
+    reason: String,
+}
+
+// ws://error 事件负载；协议校验失败时 protocol 携带结构化错误
+#[derive(Clone, Serialize)]
+pub struct WsError {
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    protocol: Option<ProtocolError>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+}
+
+// 一次连接会话的结束原因
+enum SessionEnd {
+    Shutdown,
+    Lost(WsClosed),
+}
+
+// 连接在后台运行时依赖的外部环境：推送事件、读取会话令牌和日志脱敏规则
+pub trait WsHost: EventSink {
+    // 只对后端的 WebSocket 服务返回令牌
+    fn session_token(&self, url: &str) -> Option<String>;
+    fn log_redaction(&self) -> LogRedaction;
+}
+
+impl WsHost for AppHandle {
+    fn session_token(&self, url: &str) -> Option<String> {
+        if !self.try_state::<HttpState>()?.settings().is_backend(url) {
+            return None;
+        }
+        self.try_state::<SessionState>()?.access_token()
+    }
+
+    // 与 HTTP 请求共用 http-settings.json 中的脱敏规则
+    fn log_redaction(&self) -> LogRedaction {
+        self.try_state::<HttpState>()
+            .map(|http| http.settings().log_redaction)
+            .unwrap_or_default()
+    }
+}
+
+// 初始消息和心跳模板：`{ts}` 替换为秒级时间戳，`{token}` 替换为会话令牌，前端无需接触令牌
+fn render_template(template: &str, token: Option<&str>) -> String {
+    template
+        .replace("{ts}", &chrono::Utc::now().timestamp().to_string())
+        .replace("{token}", token.unwrap_or_default())
+}
+
+impl Connection {
+    async fn connect(&self, host: &dyn WsHost) -> Result<WsStream, String> {
+        // 由 URL 构建客户端握手请求（支持 ws:// 与 wss://），并附带自定义请求头
+        let mut request = self
+            .url
+            .as_str()
+            .into_client_request()
+            .map_err(|e| format!("Invalid WebSocket URL: {}", e))?;
+        for (name, value) in self.headers.iter() {
+            request.headers_mut().insert(name.clone(), value.clone());
+        }
+        // 每次（重）连接都使用最新的会话令牌
+        if !request.headers().contains_key(AUTHORIZATION) {
+            if let Some(header) = host
+                .session_token(&self.url)
+                .and_then(|token| HeaderValue::from_str(&format!("Bearer {}", token)).ok())
+            {
+                request.headers_mut().insert(AUTHORIZATION, header);
+            }
+        }
+
+        let (ws_stream, response) = connect_async(request)
+            .await
+            .map_err(|e| format!("Error during WebSocket handshake: {}", e))?;
+        info!("WebSocket handshake completed with status: {}", response.status());
+        Ok(ws_stream)
+    }
+
+    async fn send(&self, message: Message) -> Result<(), String> {
+        let mut sink = self.sink.lock().await;
+        match sink.as_mut() {
+            Some(sink) => {
+                let len = message.len() as u64;
+                sink.send(message)
+                    .await
+                    .map_err(|e| format!("Failed to send message: {}", e))?;
+                self.bytes_sent.fetch_add(len, Ordering::Relaxed);
+                Ok(())
+            }
+            None => Err("WebSocket is not connected.".into()),
+        }
+    }
+
+    fn emit<S: Serialize + Clone>(&self, host: &dyn WsHost, event: &str, payload: S) {
+        events::emit(
+            host,
+            event,
+            SessionEvent {
+                session_id: self.id.clone(),
+                payload,
+            },
+        );
+    }
+
+    async fn update_status(&self, host: &dyn WsHost, update: impl FnOnce(&mut WsStatus)) {
+        let snapshot = {
+            let mut status = self.status.lock().await;
+            update(&mut status);
+            status.clone()
+        };
+        self.emit(host, EVENT_STATUS, snapshot);
+    }
+
+    // 已连接且无积压时直接发送，否则放入发件箱等待重连后补发
+    async fn deliver(&self, host: &dyn WsHost, text: String, ttl: Option<Duration>) -> Result<Delivery, String> {
+        let status = self.status.lock().await.status;
+        if matches!(status, ConnectionStatus::Closed | ConnectionStatus::Failed) {
+            return Err(format!("WebSocket session {} has stopped reconnecting.", self.id));
+        }
+
+        let mut outbox = self.outbox.lock().await;
+        if outbox.queue.is_empty() {
+            match self.send(Message::Text(text.clone())).await {
+                Ok(()) => return Ok(Delivery::Sent),
+                Err(e) => info!("Queueing message for session {}: {}", self.id, e),
+            }
+        }
+
+        if outbox.push(QueuedMessage {
+            text,
+            expires_at: ttl.map(|ttl| Instant::now() + ttl),
+        }) {
+            error!("Outbox of session {} is full, dropped the oldest message.", self.id);
+        }
+        let stats = outbox.stats();
+        drop(outbox);
+
+        self.emit(host, EVENT_OUTBOX, stats);
+        Ok(Delivery::Queued)
+    }
+
+    // 按入队顺序补发积压消息，跳过已过期的；发送失败时把该消息放回队首
+    async fn flush_outbox(&self, host: &dyn WsHost) -> Result<(), String> {
+        let mut outbox = self.outbox.lock().await;
+        if outbox.queue.is_empty() {
+            return Ok(());
+        }
+
+        let now = Instant::now();
+        let mut flushed = 0;
+        let mut result = Ok(());
+        while let Some(queued) = outbox.queue.pop_front() {
+            if matches!(queued.expires_at, Some(at) if at <= now) {
+                outbox.expired += 1;
+                continue;
+            }
+            if let Err(e) = self.send(Message::Text(queued.text.clone())).await {
+                outbox.queue.push_front(queued);
+                result = Err(e);
+                break;
+            }
+            flushed += 1;
+        }
+        let stats = outbox.stats();
+        drop(outbox);
+
+        info!("Flushed {} queued message(s) on session {}", flushed, self.id);
+        self.emit(host, EVENT_OUTBOX, stats);
+        result
+    }
+
+    // 若收到的帧携带某个等待中请求的 id（顶层或 data 内），将其交给对应的调用方
+    async fn resolve_pending(&self, frame: &Value) -> bool {
+        let mut pending = self.pending.lock().await;
+        let matched = pending
+            .iter()
+            .find(|(id, request)| {
+                let field = request.id_field.as_str();
+                [frame.get(field), frame.get("data").and_then(|data| data.get(field))]
+                    .iter()
+                    .flatten()
+                    .any(|value| value.as_str() == Some(id.as_str()))
+            })
+            .map(|(id, _)| id.clone());
+
+        match matched.and_then(|id| pending.remove(&id)) {
+            Some(request) => {
+                let _ = request.reply.send(frame.clone());
+                true
+            }
+            None => false,
+        }
+    }
+
+    async fn info(&self) -> WsSessionInfo {
+        let outbox = self.outbox.lock().await.stats();
+        let status = self.status.lock().await;
+        WsSessionInfo {
+            session_id: self.id.clone(),
+            url: self.url.clone(),
+            status: status.status,
+            uptime_ms: status
+                .connected_since
+                .filter(|_| status.status == ConnectionStatus::Connected)
+                .map(|since| since.elapsed().as_millis() as u64),
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+            bytes_received: self.bytes_received.load(Ordering::Relaxed),
+            outbox,
+            last_error: status.last_error.clone(),
+        }
+    }
+}
+
+// 后台守护任务：维持连接、发送心跳，并在断线后按退避策略重连
+async fn supervise(host: Arc<dyn WsHost>, connection: Arc<Connection>, first: WsStream) {
+    let host = host.as_ref();
+    let mut shutdown = connection.shutdown.subscribe();
+    let mut next = Some(first);
+    let mut attempt: u32 = 0;
+
+    loop {
+        if *shutdown.borrow() {
+            break;
+        }
+
+        let ws_stream = match next.take() {
+            Some(ws_stream) => ws_stream,
+            None => match connection.connect(host).await {
+                Ok(ws_stream) => ws_stream,
+                Err(e) => {
+                    error!("Reconnect attempt {} failed: {}", attempt, e);
+                    connection
+                        .update_status(host, |s| s.last_error = Some(e.clone()))
+                        .await;
+                    if !wait_before_retry(host, &connection, &mut shutdown, &mut attempt).await {
+                        break;
+                    }
+                    continue;
+                }
+            },
+        };
+
+        attempt = 0;
+        let (mut sink, source) = ws_stream.split();
+
+        // 每次（重新）建立连接后都重放初始消息
+        let initial_message = render_template(
+            &connection.initial_message,
+            host.session_token(&connection.url).as_deref(),
+        );
+        let replay = sink.send(Message::Text(initial_message.clone())).await;
+        if let Err(e) = &replay {
+            error!("Failed to send initial message: {}", e);
+        } else {
+            info!("Successfully sent initial message: {}", host.log_redaction().text(&initial_message));
+        }
+        *connection.sink.lock().await = Some(sink);
+
+        connection
+            .update_status(host, |s| {
+                s.status = ConnectionStatus::Connected;
+                s.attempt = 0;
+                s.connected_at = Some(chrono::Utc::now().to_rfc3339());
+                s.connected_since = Some(Instant::now());
+                s.next_retry_ms = None;
+            })
+            .await;
+
+        let ready = match replay {
+            Ok(()) => connection.flush_outbox(host).await,
+            Err(e) => Err(e.to_string()),
+        };
+        let end = match ready {
+            Ok(()) => run_session(host, &connection, source, &mut shutdown).await,
+            Err(reason) => SessionEnd::Lost(WsClosed { code: None, reason }),
+        };
+        connection.sink.lock().await.take();
+
+        match end {
+            SessionEnd::Shutdown => break,
+            SessionEnd::Lost(closed) => {
+                info!("WebSocket connection lost: {}", closed.reason);
+                connection
+                    .update_status(host, |s| s.last_error = Some(closed.reason.clone()))
+                    .await;
+                connection.emit(host, EVENT_CLOSED, closed);
+                if !wait_before_retry(host, &connection, &mut shutdown, &mut attempt).await {
+                    break;
+                }
+            }
+        }
+    }
+
+    let closed = *shutdown.borrow();
+    connection
+        .update_status(host, |s| {
+            if closed {
+                s.status = ConnectionStatus::Closed;
+            }
+            s.next_retry_ms = None;
+        })
+        .await;
+    if closed {
+        connection.emit(
+            host,
+            EVENT_CLOSED,
+            WsClosed {
+                code: None,
+                reason: "Connection closed by client.".into(),
+            },
+        );
+    }
+    info!("WebSocket supervisor for session {} stopped.", connection.id);
+}
+
+// 按退避策略等待下一次重连；返回 false 表示不再重连（已关闭、已禁用或次数用尽）
+async fn wait_before_retry(
+    host: &dyn WsHost,
+    connection: &Connection,
+    shutdown: &mut watch::Receiver<bool>,
+    attempt: &mut u32,
+) -> bool {
+    let policy = &connection.reconnect;
+    *attempt += 1;
+    let exhausted = matches!(policy.max_attempts, Some(max) if *attempt > max);
+    if !policy.enabled || exhausted {
+        error!("Giving up reconnecting WebSocket after {} attempt(s).", *attempt - 1);
+        connection
+            .update_status(host, |s| s.status = ConnectionStatus::Failed)
+            .await;
+        return false;
+    }
+
+    let delay = policy.delay(*attempt);
+    info!("Reconnecting WebSocket in {:?} (attempt {})", delay, *attempt);
+    let current = *attempt;
+    connection
+        .update_status(host, |s| {
+            s.status = ConnectionStatus::Reconnecting;
+            s.attempt = current;
+            s.next_retry_ms = Some(delay.as_millis() as u64);
+        })
+        .await;
+
+    tokio::select! {
+        _ = tokio::time::sleep(delay) => {
+            connection
+                .update_status(host, |s| {
+                    s.status = ConnectionStatus::Connecting;
+                    s.next_retry_ms = None;
+                })
+                .await;
+            true
+        }
+        _ = shutdown.changed() => false,
+    }
+}
+
+// 单次连接的读取与心跳循环：转发服务端推送的帧，并检测心跳超时
+async fn run_session(
+    host: &dyn WsHost,
+    connection: &Connection,
+    mut source: WsSource,
+    shutdown: &mut watch::Receiver<bool>,
+) -> SessionEnd {
+    let heartbeat = &connection.heartbeat;
+    let period = Duration::from_millis(heartbeat.interval_ms).max(MIN_HEARTBEAT_INTERVAL);
+    let mut ticker = tokio::time::interval_at(Instant::now() + period, period);
+    let mut awaiting_reply = false;
+    let mut missed: u32 = 0;
+
+    loop {
+        tokio::select! {
+            _ = shutdown.changed() => {
+                if let Some(mut sink) = connection.sink.lock().await.take() {
+                    if let Err(e) = sink.close().await {
+                        error!("Failed to close WebSocket connection: {}", e);
+                    }
+                }
+                return SessionEnd::Shutdown;
+            }
+            frame = source.next() => {
+                let frame = match frame {
+                    Some(Ok(frame)) => frame,
+                    Some(Err(e)) => {
+                        error!("WebSocket read error: {}", e);
+                        connection.emit(
+                            host,
+                            EVENT_ERROR,
+                            WsError {
+                                message: e.to_string(),
+                                protocol: None,
+                                text: None,
+                            },
+                        );
+                        return SessionEnd::Lost(WsClosed { code: None, reason: e.to_string() });
+                    }
+                    None => {
+                        return SessionEnd::Lost(WsClosed {
+                            code: None,
+                            reason: "Connection closed by peer.".into(),
+                        });
+                    }
+                };
+
+                // 收到任何帧都说明连接仍然存活
+                awaiting_reply = false;
+                missed = 0;
+                connection.bytes_received.fetch_add(frame.len() as u64, Ordering::Relaxed);
+
+                match frame {
+                    Message::Text(text) => {
+                        info!("Received message: {}", host.log_redaction().text(&text));
+                        let data = serde_json::from_str::<Value>(&text).ok();
+                        // 已被 websocket_request 认领的回复不再广播
+                        if let Some(frame) = &data {
+                            if connection.resolve_pending(frame).await {
+                                continue;
+                            }
+                        }
+
+                        let message = if connection.validate {
+                            match ServerMessage::parse(&text) {
+                                Ok(message) => Some(message),
+                                Err(e) => {
+                                    error!("Rejected malformed frame: {}", e);
+                                    connection.emit(
+                                        host,
+                                        EVENT_ERROR,
+                                        WsError {
+                                            message: e.to_string(),
+                                            protocol: Some(e),
+                                            text: Some(text),
+                                        },
+                                    );
+                                    continue;
+                                }
+                            }
+                        } else {
+                            None
+                        };
+                        connection.emit(host, EVENT_MESSAGE, WsFrame::Text { text, data, message });
+                    }
+                    Message::Binary(data) => {
+                        info!("Received binary message ({} bytes)", data.len());
+                        connection.emit(host, EVENT_MESSAGE, WsFrame::Binary { data });
+                    }
+                    Message::Ping(payload) => {
+                        if let Err(e) = connection.send(Message::Pong(payload)).await {
+                            error!("Failed to answer ping: {}", e);
+                        }
+                    }
+                    Message::Pong(_) => {}
+                    Message::Close(frame) => {
+                        let mut closed = WsClosed {
+                            code: None,
+                            reason: "Connection closed by peer.".into(),
+                        };
+                        if let Some(frame) = frame {
+                            closed.code = Some(u16::from(frame.code));
+                            closed.reason = frame.reason.into_owned();
+                        }
+                        return SessionEnd::Lost(closed);
+                    }
+                }
+            }
+            _ = ticker.tick() => {
+                if awaiting_reply {
+                    missed += 1;
+                    if missed >= heartbeat.max_missed.max(1) {
+                        error!("WebSocket heartbeat timed out after {} missed pong(s).", missed);
+                        return SessionEnd::Lost(WsClosed {
+                            code: None,
+                            reason: "Heartbeat timed out.".into(),
+                        });
+                    }
+                }
+
+                let mut beat = connection.send(Message::Ping(Vec::new())).await;
+                if let (Ok(()), Some(template)) = (&beat, &heartbeat.payload) {
+                    let token = host.session_token(&connection.url);
+                    beat = connection.send(Message::Text(render_template(template, token.as_deref()))).await;
+                }
+                if let Err(e) = beat {
+                    error!("Failed to send heartbeat: {}", e);
+                    return SessionEnd::Lost(WsClosed { code: None, reason: e });
+                }
+                awaiting_reply = true;
+            }
+        }
+    }
+}
+
+// 停止守护任务并等待其退出，超时则直接终止
+async fn shutdown_connection(active: ActiveConnection) {
+    let ActiveConnection {
+        connection,
+        mut supervisor,
+    } = active;
+    let _ = connection.shutdown.send(true);
+    if tokio::time::timeout(CLOSE_TIMEOUT, &mut supervisor).await.is_err() {
+        error!("WebSocket supervisor did not stop in time, aborting.");
+        supervisor.abort();
+    }
+}
+
+// 查找指定会话的连接
+async fn find_connection(state: &Mutex<WebSocketState>, session_id: &str) -> Option<Arc<Connection>> {
+    let ws_state = state.lock().await;
+    ws_state
+        .sessions
+        .get(session_id)
+        .map(|active| active.connection.clone())
+}
+
+// start_websocket_connection 的参数
+pub struct ConnectOptions {
+    pub session_id: String,
+    pub url: String,
+    pub headers: HashMap<String, String>,
+    pub initial_message: String,
+    pub heartbeat: Option<HeartbeatConfig>,
+    pub reconnect: Option<ReconnectPolicy>,
+    pub outbox_capacity: Option<usize>,
+    pub validate: Option<bool>,
+}
+
+// 以下函数不依赖 Tauri State，命令只负责转发参数
+
+// 完成首次握手并启动后台守护任务
+pub async fn open_session(
+    host: Arc<dyn WsHost>,
+    state: &Mutex<WebSocketState>,
+    options: ConnectOptions,
+) -> Result<String, AppError> {
+    let ConnectOptions {
+        session_id,
+        url,
+        headers,
+        initial_message,
+        heartbeat,
+        reconnect,
+        outbox_capacity,
+        validate,
+    } = options;
+    let redaction = host.log_redaction();
+    info!("Starting WebSocket session {} with URL: {}", session_id, redaction.text(&url));
+    info!("Headers: {:?}", redaction.pairs(&headers));
+    info!("Initial message: {}", redaction.text(&initial_message));
+
+    // Set headers from provided values
+    let mut req_headers = HeaderMap::new();
+    for (key, value) in headers {
+        if let Ok(header_name) = HeaderName::from_bytes(key.as_bytes()) {
+            if let Ok(header_value) = HeaderValue::from_str(&value) {
+                req_headers.insert(header_name, header_value);
+            } else {
+                error!("Invalid header value for {}", key);
+            }
+        } else {
+            error!("Invalid header name: {}", key);
+        }
+    }
+
+    let (shutdown, _) = watch::channel(false);
+    let connection = Arc::new(Connection {
+        id: session_id.clone(),
+        url: url.clone(),
+        headers: req_headers,
+        initial_message,
+        validate: validate.unwrap_or(false),
+        heartbeat: heartbeat.unwrap_or_default(),
+        reconnect: reconnect.unwrap_or_default(),
+        sink: Mutex::new(None),
+        outbox: Mutex::new(Outbox::new(outbox_capacity.unwrap_or(DEFAULT_OUTBOX_CAPACITY))),
+        status: Mutex::new(WsStatus {
+            status: ConnectionStatus::Connecting,
+            url: Some(url),
+            ..Default::default()
+        }),
+        shutdown,
+        bytes_sent: AtomicU64::new(0),
+        bytes_received: AtomicU64::new(0),
+        pending: Mutex::new(HashMap::new()),
+    });
+
+    // 首次连接在命令内完成，握手失败直接返回给调用方
+    let ws_stream = connection.connect(host.as_ref()).await.map_err(AppError::WebSocket)?;
+
+    let previous = {
+        let mut ws_state = state.lock().await;
+        let supervisor = tokio::spawn(supervise(host, connection.clone(), ws_stream));
+        ws_state.sessions.insert(
+            session_id.clone(),
+            ActiveConnection {
+                connection,
+                supervisor,
+            },
+        )
+    };
+
+    // 同一会话 id 已有连接时关闭旧连接，避免悬空的 socket 与后台任务
+    if let Some(previous) = previous {
+        info!("Replacing existing WebSocket session {}", session_id);
+        shutdown_connection(previous).await;
+    }
+
+    info!("WebSocket session {} established.", session_id);
+    Ok("WebSocket connection established.".into())
+}
+
+// keys 不为空时为消息追加 ts 和 sn 签名
+pub async fn send_message(
+    host: &dyn WsHost,
+    state: &Mutex<WebSocketState>,
+    keys: Option<&SigningKeys>,
+    session_id: &str,
+    message: String,
+    ttl: Option<Duration>,
+) -> Result<String, AppError> {
+    // 打印请求参数
+    let redaction = host.log_redaction();
+    info!("Sending WebSocket message on session {}: {}", session_id, redaction.text(&message));
+
+    if let Some(connection) = find_connection(state, session_id).await {
+        if connection.validate {
+            ClientMessage::parse(&message).map_err(|e| {
+                error!("Rejected outgoing message: {}", e);
+                AppError::from(e)
+            })?;
+        }
+
+        let message = match keys {
+            Some(keys) => {
+                let mut value: Value = serde_json::from_str(&message)
+                    .map_err(|e| AppError::Validation(format!("Signed messages must be JSON: {}", e)))?;
+                let object = value
+                    .as_object_mut()
+                    .ok_or_else(|| AppError::Validation("Signed messages must be JSON objects.".to_string()))?;
+                keys.sign_object(object, signing::timestamp());
+                value.to_string()
+            }
+            None => message,
+        };
+
+        // 发送消息
+        let send_result = connection.deliver(host, message.clone(), ttl).await;
+
+        // 打印发送消息的结果
+        if let Err(e) = &send_result {
+            error!("{}", e);
+        }
+
+        match send_result.map_err(AppError::WebSocket)? {
+            Delivery::Sent => {
+                info!("Message sent: {}", redaction.text(&message));
+                Ok("Message sent.".into())
+            }
+            Delivery::Queued => {
+                info!("Message queued: {}", redaction.text(&message));
+                Ok("Message queued.".into())
+            }
+        }
+    } else {
+        let error_message = format!("WebSocket session {} is not connected.", session_id);
+        error!("{}", error_message);
+        Err(AppError::WebSocket(error_message))
+    }
+}
+
+pub async fn close_session(state: &Mutex<WebSocketState>, session_id: &str) -> Result<String, AppError> {
+    info!("Attempting to close WebSocket session {}...", session_id);
+
+    let active = state.lock().await.sessions.remove(session_id);
+
+    if let Some(active) = active {
+        shutdown_connection(active).await;
+
+        info!("WebSocket session {} closed.", session_id);
+        Ok("WebSocket connection closed.".into())
+    } else {
+        let error_message = format!("WebSocket session {} is not connected.", session_id);
+        error!("{}", error_message);
+        Err(AppError::WebSocket(error_message))
+    }
+}
+
+pub async fn session_status(state: &Mutex<WebSocketState>, session_id: &str) -> WsStatus {
+    match find_connection(state, session_id).await {
+        Some(connection) => connection.status.lock().await.clone(),
+        None => WsStatus::default(),
+    }
+}
+
+pub async fn list_sessions(state: &Mutex<WebSocketState>) -> Vec<WsSessionInfo> {
+    let connections: Vec<Arc<Connection>> = {
+        let ws_state = state.lock().await;
+        ws_state
+            .sessions
+            .values()
+            .map(|active| active.connection.clone())
+            .collect()
+    };
+
+    let mut sessions = Vec::with_capacity(connections.len());
+    for connection in connections {
+        sessions.push(connection.info().await);
+    }
+    sessions.sort_by(|a, b| a.session_id.cmp(&b.session_id));
+    sessions
+}
+
+// 发送带请求 id 的 JSON 消息，并等待服务端返回携带相同 id 的帧
+pub async fn request(
+    host: &dyn WsHost,
+    state: &Mutex<WebSocketState>,
+    keys: Option<&SigningKeys>,
+    session_id: &str,
+    message: Value,
+    id_field: Option<String>,
+    timeout: Option<Duration>,
+) -> Result<Value, AppError> {
+    let connection = find_connection(state, session_id)
+        .await
+        .ok_or_else(|| AppError::WebSocket(format!("WebSocket session {} is not connected.", session_id)))?;
+
+    let id_field = id_field.unwrap_or_else(|| DEFAULT_REQUEST_ID_FIELD.to_string());
+    let timeout = timeout.unwrap_or(DEFAULT_REQUEST_TIMEOUT);
+
+    if connection.validate {
+        ClientMessage::from_value(message.clone()).map_err(|e| {
+            error!("Rejected outgoing request: {}", e);
+            AppError::from(e)
+        })?;
+    }
+
+    let mut message = message;
+    let object = message
+        .as_object_mut()
+        .ok_or_else(|| AppError::Validation("websocket_request expects a JSON object message.".to_string()))?;
+    // 调用方已自带 id 时沿用，否则生成新的
+    let request_id = match object.get(&id_field).and_then(Value::as_str) {
+        Some(id) => id.to_string(),
+        None => {
+            let id = uuid::Uuid::now_v7().to_string();
+            object.insert(id_field.clone(), Value::String(id.clone()));
+            id
+        }
+    };
+    // 签名覆盖请求 id
+    if let Some(keys) = keys {
+        keys.sign_object(object, signing::timestamp());
+    }
+
+    let (reply, response) = oneshot::channel();
+    connection.pending.lock().await.insert(
+        request_id.clone(),
+        PendingRequest {
+            id_field,
+            reply,
+        },
+    );
+
+    info!(
+        "Sending WebSocket request {} on session {}: {}",
+        request_id,
+        session_id,
+        host.log_redaction().json(&message)
+    );
+    // 断线时请求同样进入发件箱，重连后补发，超时前仍可收到回复
+    if let Err(e) = connection.deliver(host, message.to_string(), Some(timeout)).await {
+        connection.pending.lock().await.remove(&request_id);
+        error!("{}", e);
+        return Err(AppError::WebSocket(e));
+    }
+
+    match tokio::time::timeout(timeout, response).await {
+        Ok(Ok(frame)) => {
+            info!("WebSocket request {} answered.", request_id);
+            Ok(frame)
+        }
+        Ok(Err(_)) => Err(AppError::WebSocket(format!("WebSocket request {} was cancelled.", request_id))),
+        Err(_) => {
+            connection.pending.lock().await.remove(&request_id);
+            let error_message = format!("WebSocket request {} timed out after {:?}.", request_id, timeout);
+            error!("{}", error_message);
+            Err(AppError::WebSocket(error_message))
+        }
+    }
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn start_websocket_connection(
+    app: AppHandle,
+    state: State<'_, Arc<Mutex<WebSocketState>>>,
+    session_id: String,
+    url: String,
+    headers: HashMap<String, String>,
+    initial_message: String, // 新增参数，初始消息
+    heartbeat: Option<HeartbeatConfig>,
+    reconnect: Option<ReconnectPolicy>,
+    outbox_capacity: Option<usize>,
+    validate: Option<bool>, // 按邮件任务协议校验收发的消息，默认关闭，协议外的帧原样转发
+) -> Result<String, AppError> {
+    let options = ConnectOptions {
+        session_id,
+        url,
+        headers,
+        initial_message,
+        heartbeat,
+        reconnect,
+        outbox_capacity,
+        validate,
+    };
+    open_session(Arc::new(app), &state, options).await
+}
+
+#[tauri::command]
+pub async fn send_websocket_message(
+    app: AppHandle,
+    state: State<'_, Arc<Mutex<WebSocketState>>>,
+    session_id: String,
+    message: String,
+    ttl_ms: Option<u64>, // 断线排队时的有效期，过期后不再补发
+    sign: Option<bool>,  // 为消息追加 ts 和 sn 签名，与 HTTP 请求一致
+    signing: State<'_, SigningState>,
+) -> Result<String, AppError> {
+    let keys = if sign.unwrap_or(false) { Some(signing.keys()?) } else { None };
+    let ttl = ttl_ms.map(Duration::from_millis);
+    send_message(&app, &state, keys.as_ref(), &session_id, message, ttl).await
+}
+
+#[tauri::command]
+pub async fn close_websocket_connection(
+    state: State<'_, Arc<Mutex<WebSocketState>>>,
+    session_id: String,
+) -> Result<String, AppError> {
+    close_session(&state, &session_id).await
+}
+
+#[tauri::command]
+pub async fn get_websocket_status(
+    state: State<'_, Arc<Mutex<WebSocketState>>>,
+    session_id: String,
+) -> Result<WsStatus, AppError> {
+    Ok(session_status(&state, &session_id).await)
+}
+
+#[tauri::command]
+pub async fn list_websocket_sessions(
+    state: State<'_, Arc<Mutex<WebSocketState>>>,
+) -> Result<Vec<WsSessionInfo>, AppError> {
+    Ok(list_sessions(&state).await)
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn websocket_request(
+    app: AppHandle,
+    state: State<'_, Arc<Mutex<WebSocketState>>>,
+    session_id: String,
+    message: Value,
+    id_field: Option<String>,
+    timeout_ms: Option<u64>,
+    sign: Option<bool>,
+    signing: State<'_, SigningState>,
+) -> Result<Value, AppError> {
+    let keys = if sign.unwrap_or(false) { Some(signing.keys()?) } else { None };
+    let timeout = timeout_ms.map(Duration::from_millis);
+    request(&app, &state, keys.as_ref(), &session_id, message, id_field, timeout).await
+}
@@ -0,0 +1,190 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Map, Value};
+use std::fmt;
+
+// 邮箱连接失败（代理、网络、服务器等）的错误码
+pub const CONNECT_ERROR_CODES: [i64; 4] = [400002, 400003, 400004, 400006];
+// 邮箱登录失败（账号或密码错误）的错误码
+pub const LOGIN_ERROR_CODES: [i64; 1] = [400005];
+
+// 客户端发往服务端的消息，以 `type` 字段区分
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ClientMessage {
+    Heartbeat { token: String },
+    Login { data: TaskTarget },
+    InboxMails { data: TaskTarget },
+}
+
+// 登录、收信等批量任务的目标邮箱
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TaskTarget {
+    pub gid: i64,
+    pub status_id: i64,
+    // 以逗号分隔的邮箱 id 列表，例如 "1,2,3"
+    pub ids: String,
+}
+
+// 服务端推送的消息：code 大于 0 表示失败
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ServerMessage {
+    pub code: i64,
+    #[serde(default)]
+    pub message: String,
+    #[serde(skip_deserializing, default)]
+    pub kind: ErrorKind,
+    #[serde(default)]
+    pub data: Option<ServerEvent>,
+}
+
+// 供前端直接 switch 的错误分类
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ErrorKind {
+    #[default]
+    Ok,
+    ConnectFailed,
+    LoginFailed,
+    Other,
+}
+
+impl ErrorKind {
+    pub fn from_code(code: i64) -> Self {
+        if code <= 0 {
+            ErrorKind::Ok
+        } else if CONNECT_ERROR_CODES.contains(&code) {
+            ErrorKind::ConnectFailed
+        } else if LOGIN_ERROR_CODES.contains(&code) {
+            ErrorKind::LoginFailed
+        } else {
+            ErrorKind::Other
+        }
+    }
+}
+
+// 服务端推送的业务事件，以 data.action_type 区分
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "action_type", rename_all = "camelCase")]
+pub enum ServerEvent {
+    Login {
+        id: i64,
+        gid: i64,
+        status_id: i64,
+        mailbox: String,
+    },
+    UseProxy {
+        id: i64,
+        mailbox: String,
+        proxy_host: String,
+        #[serde(deserialize_with = "string_or_number")]
+        proxy_port: String,
+        #[serde(default)]
+        proxy_user: String,
+    },
+    NewMail {
+        #[serde(flatten)]
+        payload: Map<String, Value>,
+    },
+    InboxMails {
+        #[serde(flatten)]
+        payload: Map<String, Value>,
+    },
+    BulkMails {
+        #[serde(flatten)]
+        payload: Map<String, Value>,
+    },
+    AllMails {
+        #[serde(flatten)]
+        payload: Map<String, Value>,
+    },
+    MailDetail {
+        #[serde(flatten)]
+        payload: Map<String, Value>,
+    },
+}
+
+// 协议校验失败的结构化错误，会原样发送给前端
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum ProtocolError {
+    Json { detail: String },
+    Schema { detail: String },
+    Field { field: String, detail: String },
+}
+
+impl fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProtocolError::Json { detail } => write!(f, "Invalid JSON: {}", detail),
+            ProtocolError::Schema { detail } => write!(f, "Invalid message: {}", detail),
+            ProtocolError::Field { field, detail } => write!(f, "Invalid field {}: {}", field, detail),
+        }
+    }
+}
+
+impl std::error::Error for ProtocolError {}
+
+fn string_or_number<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    match Value::deserialize(deserializer)? {
+        Value::String(value) => Ok(value),
+        Value::Number(value) => Ok(value.to_string()),
+        other => Err(serde::de::Error::custom(format!("expected string or number, got {}", other))),
+    }
+}
+
+fn parse_json(text: &str) -> Result<Value, ProtocolError> {
+    serde_json::from_str(text).map_err(|e| ProtocolError::Json { detail: e.to_string() })
+}
+
+impl TaskTarget {
+    fn validate(&self) -> Result<(), ProtocolError> {
+        let invalid = |detail: &str| ProtocolError::Field {
+            field: "data.ids".into(),
+            detail: detail.into(),
+        };
+        if self.ids.trim().is_empty() {
+            return Err(invalid("at least one mailbox id is required"));
+        }
+        if self.ids.split(',').any(|id| id.trim().parse::<u64>().is_err()) {
+            return Err(invalid("must be a comma separated list of numeric ids"));
+        }
+        Ok(())
+    }
+}
+
+impl ClientMessage {
+    pub fn parse(text: &str) -> Result<Self, ProtocolError> {
+        Self::from_value(parse_json(text)?)
+    }
+
+    pub fn from_value(value: Value) -> Result<Self, ProtocolError> {
+        let message: ClientMessage = serde_json::from_value(value)
+            .map_err(|e| ProtocolError::Schema { detail: e.to_string() })?;
+        message.validate()?;
+        Ok(message)
+    }
+
+    fn validate(&self) -> Result<(), ProtocolError> {
+        match self {
+            ClientMessage::Heartbeat { token } if token.is_empty() => Err(ProtocolError::Field {
+                field: "token".into(),
+                detail: "must not be empty".into(),
+            }),
+            ClientMessage::Heartbeat { .. } => Ok(()),
+            ClientMessage::Login { data } | ClientMessage::InboxMails { data } => data.validate(),
+        }
+    }
+}
+
+impl ServerMessage {
+    pub fn parse(text: &str) -> Result<Self, ProtocolError> {
+        Self::from_value(parse_json(text)?)
+    }
+
+    pub fn from_value(value: Value) -> Result<Self, ProtocolError> {
+        let mut message: ServerMessage = serde_json::from_value(value)
+            .map_err(|e| ProtocolError::Schema { detail: e.to_string() })?;
+        message.kind = ErrorKind::from_code(message.code);
+        Ok(message)
+    }
+}
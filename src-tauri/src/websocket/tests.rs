@@ -0,0 +1,337 @@
+use super::*;
+use crate::events::RecordedEvents;
+use serde_json::json;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_tungstenite::accept_hdr_async;
+use tokio_tungstenite::tungstenite::handshake::server::{Request, Response};
+
+const SESSION: &str = "test";
+const WAIT: Duration = Duration::from_secs(5);
+
+#[derive(Default)]
+struct TestHost {
+    events: RecordedEvents,
+    token: Option<String>,
+}
+
+impl EventSink for TestHost {
+    fn emit_value(&self, event: &str, payload: Value) {
+        self.events.emit_value(event, payload);
+    }
+}
+
+impl WsHost for TestHost {
+    fn session_token(&self, _url: &str) -> Option<String> {
+        self.token.clone()
+    }
+
+    fn log_redaction(&self) -> LogRedaction {
+        LogRedaction::default()
+    }
+}
+
+// 服务端接受的一次连接：握手请求头和服务端一侧的流
+struct Accepted {
+    headers: HashMap<String, String>,
+    stream: WebSocketStream<TcpStream>,
+}
+
+struct TestServer {
+    url: String,
+    accepted: mpsc::UnboundedReceiver<Accepted>,
+}
+
+impl TestServer {
+    async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let (tx, accepted) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            while let Ok((tcp, _)) = listener.accept().await {
+                let captured = Arc::new(std::sync::Mutex::new(HashMap::new()));
+                let headers = captured.clone();
+                // 回调签名由 tungstenite 决定
+                #[allow(clippy::result_large_err)]
+                let callback = move |request: &Request, response: Response| {
+                    *headers.lock().unwrap() = request
+                        .headers()
+                        .iter()
+                        .map(|(name, value)| (name.to_string(), value.to_str().unwrap_or_default().to_string()))
+                        .collect();
+                    Ok(response)
+                };
+                if let Ok(stream) = accept_hdr_async(tcp, callback).await {
+                    let headers = captured.lock().unwrap().clone();
+                    let _ = tx.send(Accepted { headers, stream });
+                }
+            }
+        });
+        Self { url, accepted }
+    }
+
+    async fn accept(&mut self) -> Accepted {
+        tokio::time::timeout(WAIT, self.accepted.recv())
+            .await
+            .expect("no connection")
+            .unwrap()
+    }
+}
+
+// 读取下一条文本帧，跳过 Ping/Pong
+async fn next_text(stream: &mut WebSocketStream<TcpStream>) -> String {
+    loop {
+        let frame = tokio::time::timeout(WAIT, stream.next())
+            .await
+            .expect("no frame")
+            .unwrap()
+            .unwrap();
+        if let Message::Text(text) = frame {
+            return text;
+        }
+    }
+}
+
+async fn wait_for_status(state: &Mutex<WebSocketState>, expected: ConnectionStatus) {
+    let deadline = Instant::now() + WAIT;
+    while session_status(state, SESSION).await.status != expected {
+        assert!(Instant::now() < deadline, "status never became {:?}", expected);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+}
+
+fn options(url: &str) -> ConnectOptions {
+    ConnectOptions {
+        session_id: SESSION.to_string(),
+        url: url.to_string(),
+        headers: HashMap::from([("X-Client".to_string(), "tests".to_string())]),
+        initial_message: r#"{"type":"hello"}"#.to_string(),
+        heartbeat: None,
+        reconnect: Some(ReconnectPolicy {
+            initial_delay_ms: 300,
+            max_delay_ms: 300,
+            jitter: 0.0,
+            ..Default::default()
+        }),
+        outbox_capacity: None,
+        validate: Some(false),
+    }
+}
+
+#[tokio::test]
+async fn handshake_sends_headers_and_initial_message() {
+    let mut server = TestServer::start().await;
+    let host = Arc::new(TestHost {
+        token: Some("session-token".to_string()),
+        ..Default::default()
+    });
+    let state = Mutex::new(WebSocketState::default());
+    let options = ConnectOptions {
+        initial_message: r#"{"type":"heartbeat","token":"{token}"}"#.to_string(),
+        ..options(&server.url)
+    };
+
+    open_session(host.clone(), &state, options).await.unwrap();
+    let mut accepted = server.accept().await;
+
+    assert_eq!(accepted.headers["x-client"], "tests");
+    assert_eq!(accepted.headers["authorization"], "Bearer session-token");
+    assert_eq!(next_text(&mut accepted.stream).await, r#"{"type":"heartbeat","token":"session-token"}"#);
+    wait_for_status(&state, ConnectionStatus::Connected).await;
+    assert_eq!(list_sessions(&state).await.len(), 1);
+
+    close_session(&state, SESSION).await.unwrap();
+    assert_eq!(session_status(&state, SESSION).await.status, ConnectionStatus::Idle);
+    let closed = host.events.named(EVENT_CLOSED);
+    assert_eq!(closed.last().unwrap()["reason"], "Connection closed by client.");
+    assert!(close_session(&state, SESSION).await.is_err());
+}
+
+#[tokio::test]
+async fn handshake_failure_is_reported() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+    drop(listener);
+    let state = Mutex::new(WebSocketState::default());
+
+    let err = open_session(Arc::new(TestHost::default()), &state, options(&url))
+        .await
+        .unwrap_err();
+
+    assert_eq!(err.code(), "WEBSOCKET");
+    assert!(list_sessions(&state).await.is_empty());
+}
+
+#[tokio::test]
+async fn forwards_frames_and_answers_requests() {
+    let mut server = TestServer::start().await;
+    let host = Arc::new(TestHost::default());
+    let state = Mutex::new(WebSocketState::default());
+    open_session(host.clone(), &state, options(&server.url)).await.unwrap();
+    let mut accepted = server.accept().await;
+    next_text(&mut accepted.stream).await;
+
+    // 服务端推送转发为 ws://message 事件
+    accepted
+        .stream
+        .send(Message::Text(r#"{"code":0,"message":"pushed"}"#.to_string()))
+        .await
+        .unwrap();
+    // 带请求 id 的回复交给 request 的调用方
+    let server_task = tokio::spawn(async move {
+        let text = next_text(&mut accepted.stream).await;
+        let frame: Value = serde_json::from_str(&text).unwrap();
+        let reply = json!({ "request_id": frame["request_id"], "ok": true });
+        accepted.stream.send(Message::Text(reply.to_string())).await.unwrap();
+        let text = next_text(&mut accepted.stream).await;
+        (accepted, text)
+    });
+
+    let reply = request(
+        host.as_ref(),
+        &state,
+        None,
+        SESSION,
+        json!({ "type": "query" }),
+        None,
+        Some(WAIT),
+    )
+    .await
+    .unwrap();
+    assert_eq!(reply["ok"], true);
+
+    let sent = send_message(host.as_ref(), &state, None, SESSION, "plain text".to_string(), None)
+        .await
+        .unwrap();
+    assert_eq!(sent, "Message sent.");
+    let (_accepted, text) = server_task.await.unwrap();
+    assert_eq!(text, "plain text");
+
+    let messages = host.events.named(EVENT_MESSAGE);
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0]["session_id"], SESSION);
+    assert_eq!(messages[0]["data"]["message"], "pushed");
+
+    close_session(&state, SESSION).await.unwrap();
+}
+
+#[tokio::test]
+async fn forwards_frames_outside_the_schema_by_default() {
+    let mut server = TestServer::start().await;
+    let host = Arc::new(TestHost::default());
+    let state = Mutex::new(WebSocketState::default());
+    let options = ConnectOptions {
+        validate: None,
+        ..options(&server.url)
+    };
+    open_session(host.clone(), &state, options).await.unwrap();
+    let mut accepted = server.accept().await;
+    next_text(&mut accepted.stream).await;
+
+    let frame = r#"{"event":"custom","payload":[1,2]}"#;
+    accepted.stream.send(Message::Text(frame.to_string())).await.unwrap();
+    let deadline = tokio::time::Instant::now() + WAIT;
+    while host.events.named(EVENT_MESSAGE).is_empty() {
+        assert!(tokio::time::Instant::now() < deadline);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    let messages = host.events.named(EVENT_MESSAGE);
+    assert_eq!(messages[0]["text"], frame);
+    assert_eq!(messages[0]["data"]["event"], "custom");
+    assert!(host.events.named(EVENT_ERROR).is_empty());
+
+    close_session(&state, SESSION).await.unwrap();
+}
+
+#[tokio::test]
+async fn signs_outgoing_messages() {
+    let mut server = TestServer::start().await;
+    let host = Arc::new(TestHost::default());
+    let state = Mutex::new(WebSocketState::default());
+    open_session(host.clone(), &state, options(&server.url)).await.unwrap();
+    let mut accepted = server.accept().await;
+    next_text(&mut accepted.stream).await;
+
+    let keys = SigningKeys::default();
+    send_message(host.as_ref(), &state, Some(&keys), SESSION, r#"{"gid":12}"#.to_string(), None)
+        .await
+        .unwrap();
+    let frame: Value = serde_json::from_str(&next_text(&mut accepted.stream).await).unwrap();
+    assert_eq!(frame["gid"], 12);
+    assert!(frame["ts"].is_string());
+    assert!(frame["sn"].is_string());
+
+    let err = send_message(host.as_ref(), &state, Some(&keys), SESSION, "not json".to_string(), None)
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), "VALIDATION");
+
+    close_session(&state, SESSION).await.unwrap();
+}
+
+#[tokio::test]
+async fn heartbeat_pings_and_times_out() {
+    let mut server = TestServer::start().await;
+    let host = Arc::new(TestHost::default());
+    let state = Mutex::new(WebSocketState::default());
+    let mut options = options(&server.url);
+    options.heartbeat = Some(HeartbeatConfig {
+        interval_ms: 1_000,
+        payload: Some(r#"{"type":"heartbeat","ts":"{ts}"}"#.to_string()),
+        max_missed: 1,
+    });
+    options.reconnect = Some(ReconnectPolicy {
+        enabled: false,
+        ..Default::default()
+    });
+    open_session(host.clone(), &state, options).await.unwrap();
+    let mut accepted = server.accept().await;
+    next_text(&mut accepted.stream).await;
+
+    // 第一次心跳：Ping 加上模板生成的应用层心跳
+    let beat = next_text(&mut accepted.stream).await;
+    let beat: Value = serde_json::from_str(&beat).unwrap();
+    assert_eq!(beat["type"], "heartbeat");
+    assert!(beat["ts"].as_str().unwrap().parse::<i64>().is_ok());
+
+    // 服务端不再读取，也就不会回复 Pong，下一个周期判定超时
+    wait_for_status(&state, ConnectionStatus::Failed).await;
+    let closed = host.events.named(EVENT_CLOSED);
+    assert_eq!(closed.last().unwrap()["reason"], "Heartbeat timed out.");
+    drop(accepted);
+
+    close_session(&state, SESSION).await.unwrap();
+}
+
+#[tokio::test]
+async fn reconnects_and_flushes_outbox() {
+    let mut server = TestServer::start().await;
+    let host = Arc::new(TestHost::default());
+    let state = Mutex::new(WebSocketState::default());
+    open_session(host.clone(), &state, options(&server.url)).await.unwrap();
+    let mut first = server.accept().await;
+    next_text(&mut first.stream).await;
+
+    // 服务端断开后进入重连等待，期间发送的消息进入发件箱
+    first.stream.close(None).await.unwrap();
+    wait_for_status(&state, ConnectionStatus::Reconnecting).await;
+    let queued = send_message(host.as_ref(), &state, None, SESSION, "while offline".to_string(), Some(WAIT))
+        .await
+        .unwrap();
+    assert_eq!(queued, "Message queued.");
+
+    // 重连后先重放初始消息，再补发排队的消息
+    let mut second = server.accept().await;
+    assert_eq!(next_text(&mut second.stream).await, r#"{"type":"hello"}"#);
+    assert_eq!(next_text(&mut second.stream).await, "while offline");
+    wait_for_status(&state, ConnectionStatus::Connected).await;
+
+    let statuses: Vec<Value> = host.events.named(EVENT_STATUS);
+    assert!(statuses.iter().any(|s| s["status"] == "reconnecting" && s["attempt"] == 1));
+    let outbox = host.events.named(EVENT_OUTBOX);
+    assert_eq!(outbox.last().unwrap()["depth"], 0);
+    assert_eq!(list_sessions(&state).await[0].outbox.depth, 0);
+
+    close_session(&state, SESSION).await.unwrap();
+}
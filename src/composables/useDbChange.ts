@@ -0,0 +1,37 @@
+import { listen } from "@tauri-apps/api/event";
+import { onMounted, onUnmounted } from "vue";
+
+export interface DbChange {
+  entity: string;
+  id: string | null;
+  operation: "created" | "updated" | "deleted" | "imported";
+}
+
+// 监听 Rust 端在写入成功后广播的 db://change，连续的变更合并为一次回调
+export function useDbChange(
+  callback: (changes: DbChange[]) => void,
+  entities?: string[],
+  delay = 150,
+) {
+  let unlisten: (() => void) | undefined;
+  let timer: any;
+  let pending: DbChange[] = [];
+
+  onMounted(async () => {
+    unlisten = await listen<DbChange>("db://change", ({ payload }) => {
+      if (entities && !entities.includes(payload.entity)) return;
+      pending.push(payload);
+      clearTimeout(timer);
+      timer = setTimeout(() => {
+        const changes = pending;
+        pending = [];
+        callback(changes);
+      }, delay);
+    });
+  });
+
+  onUnmounted(() => {
+    clearTimeout(timer);
+    unlisten?.();
+  });
+}
@@ -1,7 +0,0 @@
-// src/host-config.ts
-export default {
-  getAES_rate() {
-    // You can use a fixed string as your AES key and IV or generate them dynamically
-    return "YEY3NKU5CgVoqxDu"; // Example AES key (16 characters for AES-128)
-  },
-};
@@ -1,143 +1,71 @@
 import { ref } from "vue";
 import { invoke } from "@tauri-apps/api/tauri";
+import { listen, UnlistenFn } from "@tauri-apps/api/event";
 import { ElMessage } from "element-plus";
 import { store, EventBus } from "@/store";
 import { changeMailboxStatusTxt, optionLog } from "@/log";
 
+// 连接由 Rust 端维护：握手时注入 Authorization，{token} 占位符在发送前替换，前端不接触令牌
+const SESSION_ID = "mail";
+const HEARTBEAT = JSON.stringify({ type: "heartbeat", token: "{token}" });
+
 class WebSocketManager {
-  private url: string = "ws://8.137.92.236:8183/ws";
-  private socket: WebSocket | null = null;
   private messageHandlers: Record<string, (message: any) => void> = {};
-  private heartbeatInterval: any;
-  private reconnectTimeout: any;
-  private token: string = "";
+  private unlisteners: UnlistenFn[] = [];
   private connectErrCodes = [400002, 400003, 400004, 400006];
   private loginErrCodes = [400005];
 
   // WebSocket 连接状态
   isConnected = ref(false);
 
-  // 初始化 WebSocket 连接
+  // 初始化 WebSocket 连接，心跳和断线重连由 Rust 端负责
   async connect(interval: number = 50000) {
     if (this.isConnected.value) {
       await this.logToRust("info", "WebSocket is already connected.");
       return;
     }
 
-    const userVal = localStorage.getItem("user");
-    const user = userVal ? JSON.parse(userVal) : null;
-    this.token = user?.token || "";
-
-    if (!this.token) {
-      await this.logToRust("error", "Token not found in localStorage");
-      return;
-    }
-
-    await this.logToRust("info", `Connecting with token: ${this.token}`);
-    await this.logToRust("info", `WebSocket URL: ${this.url}`);
-
-    // 创建 WebSocket 连接
-    this.socket = new WebSocket(
-      `${this.url}?token=${encodeURIComponent(this.token)}`
-    );
-
-    // 监听连接打开事件
-    this.socket.onopen = async () => {
-      await this.logToRust("info", "WebSocket connection established.");
-      this.isConnected.value = true;
-
-      // 发送初始心跳消息，包含 token 以进行认证
-      const heartbeatMessage = { type: "heartbeat", token: this.token };
-      this.sendMessage(heartbeatMessage);
-
-      // 设置应用层面的心跳间隔
-      this.startHeartbeat("heartbeat", interval);
-    };
-
-    // 监听消息事件
-    this.socket.onmessage = async (event) => {
-      try {
-        const message = JSON.parse(event.data);
-    
-        // 继续后续操作
-        await this.logToRust(
-          "info",
-          `Received message: ${JSON.stringify(message)}`
-        );
-        
-        this.handlerWsMsg(message);
-    
-      } catch (error) {
-        // 捕获并打印 JSON.parse 错误
-        console.error("Failed to parse JSON:", event.data);
-        console.error(error);
-      }
-    };
-
-    // 监听连接关闭事件
-    this.socket.onclose = async () => {
-      await this.logToRust("info", "WebSocket connection closed.");
-      this.isConnected.value = false;
-      this.socket = null;
-
-      // 清除心跳定时器
-      this.stopHeartbeat();
-
-      // 尝试重新连接
-      this.reconnect();
-    };
-
-    // 监听错误事件
-    this.socket.onerror = async (error) => {
-      await this.logToRust(
-        "error",
-        `WebSocket error: ${JSON.stringify(error)}`
-      );
-    };
-  }
-
-  // 开始发送心跳消息
-  startHeartbeat(messageType: string, interval: number) {
-    this.heartbeatInterval = setInterval(() => {
-      if (this.isConnected.value && this.socket) {
-        const heartbeatMessage = { type: messageType, token: this.token };
-        this.sendMessage(heartbeatMessage);
-      }
-    }, interval);
-  }
-
-  // 停止心跳消息
-  stopHeartbeat() {
-    if (this.heartbeatInterval) {
-      clearInterval(this.heartbeatInterval);
-      this.heartbeatInterval = undefined;
+    await this.listenEvents();
+    try {
+      const settings: any = await invoke("get_http_settings");
+      await invoke("start_websocket_connection", {
+        sessionId: SESSION_ID,
+        url: settings.data.websocket_url,
+        headers: {},
+        initialMessage: HEARTBEAT,
+        heartbeat: { interval_ms: interval, payload: HEARTBEAT },
+        reconnect: { enabled: true, initial_delay_ms: 5000 },
+      });
+    } catch (error) {
+      await this.logToRust("error", `WebSocket error: ${JSON.stringify(error)}`);
     }
   }
 
-  // 重连 WebSocket
-  reconnect() {
-    if (this.reconnectTimeout) {
-      clearTimeout(this.reconnectTimeout);
-    }
-    this.reconnectTimeout = setTimeout(() => {
-      this.logToRust("info", "Reconnecting WebSocket...");
-      this.connect();
-    }, 5000); // 每 5 秒尝试重连一次
+  // 只处理本会话的事件，重复调用不会重复注册
+  private async listenEvents() {
+    if (this.unlisteners.length) return;
+    this.unlisteners = await Promise.all([
+      listen<any>("ws://message", ({ payload }) => {
+        if (payload.session_id !== SESSION_ID || !payload.data) return;
+        this.handlerWsMsg(payload.data);
+      }),
+      listen<any>("ws://status", ({ payload }) => {
+        if (payload.session_id !== SESSION_ID) return;
+        this.isConnected.value = payload.status === "connected";
+      }),
+    ]);
   }
 
-  // 发送消息，接受动态参数
-  sendMessage(message: { type: string; data?: any }) {
-    if (!this.isConnected.value || !this.socket) {
-      this.logToRust(
-        "error",
-        "Cannot send message, WebSocket is not connected."
-      );
-      return;
+  // 发送消息，接受动态参数；断线期间由 Rust 端排队补发
+  async sendMessage(message: { type: string; data?: any }) {
+    try {
+      await invoke("send_websocket_message", {
+        sessionId: SESSION_ID,
+        message: JSON.stringify(message),
+      });
+    } catch (error) {
+      await this.logToRust("error", `Cannot send message: ${JSON.stringify(error)}`);
     }
-
-    const messageString = JSON.stringify(message);
-    this.logToRust("info", `Sending message: ${messageString}`);
-    this.socket.send(messageString);
   }
 
   // 注册消息类型的处理函数
@@ -146,10 +74,28 @@ class WebSocketManager {
   }
 
   // 关闭 WebSocket 连接
-  close() {
-    if (this.socket) {
-      this.logToRust("info", "Closing WebSocket connection...");
-      this.socket.close();
+  async close() {
+    this.logToRust("info", "Closing WebSocket connection...");
+    try {
+      await invoke("close_websocket_connection", { sessionId: SESSION_ID });
+    } catch (error) {
+      await this.logToRust("error", `Failed to close WebSocket: ${JSON.stringify(error)}`);
+    }
+    this.unlisteners.forEach((unlisten) => unlisten());
+    this.unlisteners = [];
+    this.isConnected.value = false;
+  }
+
+  // 启动 Rust 端的本机事件中心，返回地址和本次启动的令牌
+  async startLocalHub(port?: number) {
+    try {
+      return await invoke<{ url: string; port: number; token: string }>(
+        "start_websocket_server",
+        { port }
+      );
+    } catch (error) {
+      await this.logToRust("error", `Failed to start local hub: ${JSON.stringify(error)}`);
+      return null;
     }
   }
 
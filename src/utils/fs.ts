@@ -9,6 +9,8 @@ import {
   readBinaryFile,
 } from "@tauri-apps/api/fs";
 import { appDataDir, sep, join } from "@tauri-apps/api/path";
+import { save } from "@tauri-apps/api/dialog";
+import { invoke } from "@tauri-apps/api";
 import { error } from "tauri-plugin-log-api";
 // C:\Users\abdel\AppData\Roaming\whatisthis
 
@@ -112,3 +114,35 @@ export const getBytesArray = (file: File): Promise<Uint8Array> => {
     };
   });
 };
+
+// 导出列表的全部筛选结果，文件路径由保存对话框选择
+export const exportList = async ({
+  table,
+  format,
+  args,
+  locale,
+}: {
+  table: string;
+  format: "csv" | "xlsx";
+  args: { search: string; status?: string; created_at?: string };
+  locale: string;
+}) => {
+  const path = await save({
+    defaultPath: `${table}.${format}`,
+    filters: [{ name: format.toUpperCase(), extensions: [format] }],
+  });
+  if (!path) return null;
+  try {
+    const res = await invoke<any>("export_list_to_file", {
+      table,
+      format,
+      args: { page: 1, limit: 0, ...args },
+      path,
+      locale,
+    });
+    return res.data;
+  } catch (err: any) {
+    error("EXPORT LIST: " + err);
+    return null;
+  }
+};